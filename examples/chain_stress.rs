@@ -0,0 +1,25 @@
+//! Times a chain reaction through a packed grid of skulls.
+//!
+//! `cargo run --release --example chain_stress -- [skulls] [seed]`
+
+use exploding_skulls::headless::chain_stress;
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let skulls = args.next().and_then(|s| s.parse().ok()).unwrap_or(200);
+    let seed = args.next().and_then(|s| s.parse().ok()).unwrap_or(0);
+
+    let report = chain_stress(skulls, seed);
+    println!(
+        "{} skulls, {} killed over {} ticks",
+        report.skulls,
+        report.kills,
+        report.frame_times.len()
+    );
+    println!(
+        "total {:?}, mean {:?}, worst {:?}",
+        report.total(),
+        report.mean(),
+        report.worst()
+    );
+}
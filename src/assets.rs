@@ -1,9 +1,13 @@
+use std::{fmt, marker::PhantomData};
+
 use bevy::{
+    asset::{AssetLoader, LoadContext, io::Reader},
     diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin},
     prelude::*,
 };
 use bevy_asset_loader::prelude::*;
 use iyes_progress::{Progress, ProgressPlugin, ProgressReturningSystem, ProgressTracker};
+use serde::de::DeserializeOwned;
 
 use crate::states::AppState;
 
@@ -64,6 +68,12 @@ fn print_progress(
 
 pub trait AssetLoadingExt {
     fn load_asset_on_startup<T: AssetCollection>(&mut self) -> &mut Self;
+
+    /// Registers `A` as an asset loaded from RON files with any of the given extensions.
+    fn register_ron_asset<A: Asset + DeserializeOwned>(
+        &mut self,
+        extensions: &'static [&'static str],
+    ) -> &mut Self;
 }
 
 impl AssetLoadingExt for App {
@@ -72,4 +82,95 @@ impl AssetLoadingExt for App {
             LoadingStateConfig::new(AppState::AssetLoading).load_collection::<T>(),
         )
     }
+
+    fn register_ron_asset<A: Asset + DeserializeOwned>(
+        &mut self,
+        extensions: &'static [&'static str],
+    ) -> &mut Self {
+        self.init_asset::<A>()
+            .register_asset_loader(RonAssetLoader::<A> {
+                extensions,
+                _asset: PhantomData,
+            })
+    }
+}
+
+/// An [`AssetCollection`] of a single asset, which [`apply_ron_asset`] keeps a resource in sync with.
+pub trait SingleAssetCollection<A: Asset>: AssetCollection {
+    fn handle(&self) -> &Handle<A>;
+}
+
+/// Copies the `A` in collection `C` over the `A` resource once it has loaded, and again whenever
+/// the file changes. Until then the resource keeps its built-in default.
+pub fn apply_ron_asset<A, C>(
+    mut reader: EventReader<AssetEvent<A>>,
+    collection: Option<Res<C>>,
+    assets: Res<Assets<A>>,
+    mut resource: ResMut<A>,
+) where
+    A: Asset + Resource + Clone,
+    C: SingleAssetCollection<A>,
+{
+    let Some(collection) = collection else {
+        return;
+    };
+    let handle = collection.handle();
+    // the first load usually finishes before the collection is inserted
+    let mut changed = collection.is_added();
+    for event in reader.read() {
+        changed |= event.is_loaded_with_dependencies(handle) || event.is_modified(handle);
+    }
+    if !changed {
+        return;
+    }
+    if let Some(loaded) = assets.get(handle) {
+        info!("using {} from {:?}", A::short_type_path(), handle.path());
+        *resource = loaded.clone();
+    }
+}
+
+struct RonAssetLoader<A> {
+    extensions: &'static [&'static str],
+    _asset: PhantomData<fn() -> A>,
+}
+
+#[derive(Debug)]
+pub enum RonAssetError {
+    Io(std::io::Error),
+    Ron(ron::error::SpannedError),
+}
+
+impl fmt::Display for RonAssetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RonAssetError::Io(e) => write!(f, "failed to read asset: {e}"),
+            RonAssetError::Ron(e) => write!(f, "failed to parse asset: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for RonAssetError {}
+
+impl<A: Asset + DeserializeOwned> AssetLoader for RonAssetLoader<A> {
+    type Asset = A;
+    type Settings = ();
+    type Error = RonAssetError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<A, Self::Error> {
+        let mut bytes = Vec::new();
+        reader
+            .read_to_end(&mut bytes)
+            .await
+            .map_err(RonAssetError::Io)?;
+        ron::de::from_bytes(&bytes).map_err(RonAssetError::Ron)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        self.extensions
+    }
 }
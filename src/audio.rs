@@ -27,10 +27,13 @@ impl Plugin for AudioPlugin {
             .add_systems(
                 Update,
                 (
-                    play_shotgun_sounds,
+                    play_weapon_sounds,
                     play_explosion_sounds,
+                    play_fuse_sounds,
                     play_spawner_sounds,
                     play_player_sounds,
+                    play_pickup_sounds,
+                    play_upgrade_sounds,
                 )
                     .run_if(in_state(GameState::InGame)),
             );
@@ -166,6 +169,8 @@ struct SoundAssets {
     // explosion sounds
     #[asset(path = "sounds/explosion.ogg")]
     explosion: Handle<Sample>,
+    #[asset(path = "sounds/teeth.ogg")]
+    teeth: Handle<Sample>,
 
     // spawner sounds
     #[asset(path = "sounds/portal.ogg")]
@@ -180,18 +185,18 @@ struct SoundAssets {
     music: Handle<Sample>,
 }
 
-fn play_shotgun_sounds(
+fn play_weapon_sounds(
     mut commands: Commands,
-    mut reader: EventReader<crate::shotgun::ShotgunEvent>,
+    mut reader: EventReader<crate::weapon::WeaponEvent>,
     assets: Res<SoundAssets>,
 ) {
-    use crate::shotgun::ShotgunEvent;
+    use crate::weapon::WeaponEvent;
     for ev in reader.read() {
         match ev {
-            ShotgunEvent::Fire => {
+            WeaponEvent::Fire { .. } => {
                 commands.spawn((SamplePlayer::new(assets.gunshot.clone()), SoundEffectPool))
             }
-            ShotgunEvent::Reload => {
+            WeaponEvent::Reload { .. } => {
                 commands.spawn((SamplePlayer::new(assets.reload.clone()), SoundEffectPool))
             }
         };
@@ -212,6 +217,20 @@ fn play_explosion_sounds(
     }
 }
 
+fn play_fuse_sounds(
+    mut commands: Commands,
+    mut reader: EventReader<crate::health::FuseLitEvent>,
+    assets: Res<SoundAssets>,
+) {
+    for crate::health::FuseLitEvent { pos, .. } in reader.read() {
+        commands.spawn((
+            SamplePlayer::new(assets.teeth.clone()),
+            SpatialSoundEffectPool,
+            Transform::from_translation(*pos),
+        ));
+    }
+}
+
 fn play_spawner_sounds(
     mut commands: Commands,
     mut reader: EventReader<crate::spawner::CreateSpawnerEvent>,
@@ -241,6 +260,26 @@ fn play_player_sounds(
     }
 }
 
+fn play_pickup_sounds(
+    mut commands: Commands,
+    mut reader: EventReader<crate::pickup::PickupCollected>,
+    assets: Res<SoundAssets>,
+) {
+    for crate::pickup::PickupCollected { .. } in reader.read() {
+        commands.spawn((SamplePlayer::new(assets.reload.clone()), SoundEffectPool));
+    }
+}
+
+fn play_upgrade_sounds(
+    mut commands: Commands,
+    mut reader: EventReader<crate::shop::UpgradePurchased>,
+    assets: Res<SoundAssets>,
+) {
+    for crate::shop::UpgradePurchased { .. } in reader.read() {
+        commands.spawn((SamplePlayer::new(assets.reload.clone()), SoundEffectPool));
+    }
+}
+
 fn play_music(mut commands: Commands, assets: Res<SoundAssets>) {
     commands.spawn((SamplePlayer::new(assets.music.clone()).looping(), MusicPool));
 }
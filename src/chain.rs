@@ -0,0 +1,155 @@
+use std::{collections::BTreeMap, time::Duration};
+
+use bevy::prelude::*;
+
+use crate::{
+    explosion::ExplosionEvent,
+    health::{DamageEvent, Fuse},
+    states::{GameState, PauseState},
+};
+
+#[derive(Debug, Default)]
+pub struct ChainPlugin;
+
+impl Plugin for ChainPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ChainTracker>()
+            .add_state_scoped_event::<ChainFinished>(GameState::InGame)
+            .add_systems(OnEnter(GameState::InGame), |mut commands: Commands| {
+                commands.insert_resource(ChainTracker::default());
+            })
+            .add_systems(
+                FixedUpdate,
+                (track_chain_activity, settle_chains)
+                    .chain()
+                    .run_if(in_state(GameState::InGame).and(in_state(PauseState::Unpaused))),
+            );
+    }
+}
+
+/// Identifies a single cascade, from the damage that started it to the last explosion it caused.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ChainId(pub u64);
+
+/// What started a chain.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChainRoot {
+    /// The player shot something.
+    Shot { origin: Vec3 },
+    /// A skull touched the player.
+    Contact { pos: Vec3 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChainStats {
+    pub root: ChainRoot,
+    /// Highest `chain` value of any explosion in the cascade.
+    pub depth: u64,
+    pub kills: u32,
+    pub score: u64,
+    pub started: Duration,
+    pub last_activity: Duration,
+}
+
+impl ChainStats {
+    pub fn duration(&self) -> Duration {
+        self.last_activity.saturating_sub(self.started)
+    }
+}
+
+/// Sent once a chain has had no activity for [`ChainTracker::SETTLE_TIME`]. A burning [`Fuse`]
+/// counts as activity.
+#[derive(Debug, Clone, Copy, Event)]
+pub struct ChainFinished {
+    pub id: ChainId,
+    pub stats: ChainStats,
+}
+
+#[derive(Debug, Default, Resource)]
+pub struct ChainTracker {
+    next_id: u64,
+    // ordered, so chains finishing on the same frame are reported in a reproducible order
+    active: BTreeMap<ChainId, ChainStats>,
+}
+
+impl ChainTracker {
+    /// How long a chain has to go without damage or explosions before it's considered finished.
+    pub const SETTLE_TIME: Duration = Duration::from_millis(250);
+
+    /// Starts tracking a new chain. `now` should be the elapsed game time.
+    pub fn start(&mut self, root: ChainRoot, now: Duration) -> ChainId {
+        let id = ChainId(self.next_id);
+        self.next_id += 1;
+        self.active.insert(
+            id,
+            ChainStats {
+                root,
+                depth: 0,
+                kills: 0,
+                score: 0,
+                started: now,
+                last_activity: now,
+            },
+        );
+        id
+    }
+
+    pub fn get(&self, id: ChainId) -> Option<&ChainStats> {
+        self.active.get(&id)
+    }
+
+    pub fn active(&self) -> impl Iterator<Item = (&ChainId, &ChainStats)> {
+        self.active.iter()
+    }
+
+    /// Counts a kill worth `score` points towards the chain.
+    pub fn record_kill(&mut self, id: ChainId, score: u64) {
+        if let Some(stats) = self.active.get_mut(&id) {
+            stats.kills += 1;
+            stats.score += score;
+        }
+    }
+
+    fn touch(&mut self, id: ChainId, now: Duration) {
+        if let Some(stats) = self.active.get_mut(&id) {
+            stats.last_activity = now;
+        }
+    }
+}
+
+fn track_chain_activity(
+    time: Res<Time>,
+    mut tracker: ResMut<ChainTracker>,
+    mut damage_reader: EventReader<DamageEvent>,
+    mut explosion_reader: EventReader<ExplosionEvent>,
+    fuse_query: Query<&Fuse>,
+) {
+    let now = time.elapsed();
+    for ev in damage_reader.read() {
+        tracker.touch(ev.chain_id, now);
+    }
+    for ev in explosion_reader.read() {
+        tracker.touch(ev.chain_id, now);
+        if let Some(stats) = tracker.active.get_mut(&ev.chain_id) {
+            stats.depth = stats.depth.max(ev.chain);
+        }
+    }
+    for fuse in fuse_query.iter() {
+        tracker.touch(fuse.explosion.chain_id, now);
+    }
+}
+
+fn settle_chains(
+    time: Res<Time>,
+    mut tracker: ResMut<ChainTracker>,
+    mut writer: EventWriter<ChainFinished>,
+) {
+    let now = time.elapsed();
+    tracker.active.retain(|&id, stats| {
+        if now.saturating_sub(stats.last_activity) < ChainTracker::SETTLE_TIME {
+            return true;
+        }
+        writer.write(ChainFinished { id, stats: *stats });
+        false
+    });
+}
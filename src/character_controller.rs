@@ -1,9 +1,12 @@
+use std::time::Duration;
+
 use bevy::prelude::*;
 use bevy_rapier3d::prelude::*;
 
 use crate::{
     input::{InputAction, InputSettings, InputState},
     player::Player,
+    replay::ReplayPlayback,
     states::{GameState, PauseState},
 };
 use leafwing_input_manager::prelude::ActionState;
@@ -13,14 +16,22 @@ pub struct CharacterControllerPlugin;
 
 impl Plugin for CharacterControllerPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(
-            Update,
-            (handle_input, set_velocity)
-                .chain()
-                .in_set(PhysicsSet::SyncBackend)
-                .run_if(in_state(GameState::InGame).and(in_state(PauseState::Unpaused))),
-        )
-        .insert_resource(AccumulatedInput::default());
+        app.init_resource::<AccumulatedInput>()
+            .add_systems(
+                Update,
+                accumulate_input.run_if(
+                    in_state(GameState::InGame)
+                        .and(in_state(PauseState::Unpaused))
+                        // playback fills it in from the replay instead
+                        .and(not(resource_exists::<ReplayPlayback>)),
+                ),
+            )
+            .add_systems(
+                FixedUpdate,
+                (handle_input, set_velocity)
+                    .chain()
+                    .run_if(in_state(GameState::InGame).and(in_state(PauseState::Unpaused))),
+            );
     }
 }
 
@@ -58,59 +69,168 @@ impl ReadHeading {
 pub struct CharacterControllerState {
     pub heading: f32,
 
+    /// Turns to make on this tick.
     pub desired_turn: f32,
     pub desired_velocity: Vec3,
+
+    /// Velocity from outside forces, like explosions. Added on top of the character's own
+    /// movement and fades out over time instead of being steered away.
+    pub impulse: Vec3,
+}
+
+impl CharacterControllerState {
+    /// Fraction of the impulse that's left after a second.
+    const IMPULSE_RETAINED: f32 = 0.02;
+    /// Keeps big chains from launching things through walls.
+    const MAX_IMPULSE: f32 = 25.0;
+
+    pub fn apply_impulse(&mut self, impulse: Vec3) {
+        self.impulse = (self.impulse + impulse).clamp_length_max(Self::MAX_IMPULSE);
+    }
+}
+
+/// A short burst of speed in the direction the character is moving, with a moment of
+/// invulnerability. Tuned per character.
+#[derive(Component, Debug, Clone)]
+pub struct Dash {
+    pub speed: f32,
+    /// Seconds the burst lasts.
+    pub duration: f32,
+    /// Seconds of invulnerability from starting a dash.
+    pub invulnerability: f32,
+    /// Time between the start of one dash and the next.
+    pub cooldown: Timer,
+    /// Velocity of the dash in progress, and how long it has left.
+    active: Option<(Vec3, Timer)>,
+}
+
+impl Dash {
+    pub fn new(speed: f32, duration: f32, invulnerability: f32, cooldown: f32) -> Self {
+        let mut cooldown = Timer::from_seconds(cooldown, TimerMode::Once);
+        // ready straight away
+        cooldown.tick(cooldown.duration());
+        Dash {
+            speed,
+            duration,
+            invulnerability,
+            cooldown,
+            active: None,
+        }
+    }
+
+    pub fn ready(&self) -> bool {
+        self.cooldown.finished()
+    }
+
+    /// The velocity of the dash in progress, if there is one.
+    pub fn velocity(&self) -> Option<Vec3> {
+        self.active.as_ref().map(|(velocity, _)| *velocity)
+    }
+
+    fn start(&mut self, direction: Vec3) {
+        self.cooldown.reset();
+        self.active = Some((
+            direction * self.speed,
+            Timer::from_seconds(self.duration, TimerMode::Once),
+        ));
+    }
+
+    fn tick(&mut self, delta: Duration) {
+        self.cooldown.tick(delta);
+        let finished = self
+            .active
+            .as_mut()
+            .is_some_and(|(_, timer)| timer.tick(delta).finished());
+        if finished {
+            self.active = None;
+        }
+    }
 }
 
+impl Default for Dash {
+    fn default() -> Self {
+        Dash::new(45.0, 0.15, 0.35, 1.5)
+    }
+}
+
+/// Input for the next fixed tick. Gathered every frame in `Update`, so mouse movement isn't lost
+/// on frames without a tick or counted twice on frames with two.
 #[derive(Debug, Default, Resource)]
-struct AccumulatedInput {
-    // movement in player's frame of reference
-    movement: Vec3,
-    turn: f32,
+pub struct AccumulatedInput {
+    /// Movement in the player's frame of reference.
+    pub movement: Vec3,
+    /// Turns to make on the next tick, already scaled by the [`InputSettings`].
+    pub turn: f32,
 }
 
 impl AccumulatedInput {
-    fn clear(&mut self) {
-        self.movement = Vec3::ZERO;
-        self.turn = 0.0;
+    /// Movement from the held movement actions.
+    pub fn movement_from(input: &ActionState<InputAction>) -> Vec3 {
+        // movement is oriented as if the player is facing in the negative Z direction
+        let mut movement = Vec3::ZERO;
+        if input.pressed(&InputAction::MoveForward) {
+            movement += Vec3::NEG_Z;
+        }
+        if input.pressed(&InputAction::MoveBackward) {
+            movement += Vec3::Z;
+        }
+
+        if input.pressed(&InputAction::StrafeLeft) {
+            movement += Vec3::NEG_X;
+        }
+        if input.pressed(&InputAction::StrafeRight) {
+            movement += Vec3::X;
+        }
+        movement
     }
 }
 
-fn handle_input(
+fn accumulate_input(
+    time: Res<Time>,
+    fixed_time: Res<Time<Fixed>>,
     mut accumulated: ResMut<AccumulatedInput>,
     input: Res<ActionState<InputAction>>,
     input_settings: Res<InputSettings>,
     input_state: Res<InputState>,
-    mut query: Query<(&CharacterController, &mut CharacterControllerState, &Player)>,
 ) {
-    // movement is oriented as if the player is facing in the negative Z direction
-    if input.pressed(&InputAction::MoveForward) {
-        accumulated.movement += Vec3::NEG_Z;
-    }
-    if input.pressed(&InputAction::MoveBackward) {
-        accumulated.movement += Vec3::Z;
-    }
-
-    if input.pressed(&InputAction::StrafeLeft) {
-        accumulated.movement += Vec3::NEG_X;
-    }
-    if input.pressed(&InputAction::StrafeRight) {
-        accumulated.movement += Vec3::X;
-    }
+    accumulated.movement = AccumulatedInput::movement_from(&input);
 
+    let mut turn = 0.0;
     if input.pressed(&InputAction::TurnLeft) {
-        accumulated.turn += 1.0;
+        turn += time.delta_secs();
     }
     if input.pressed(&InputAction::TurnRight) {
-        accumulated.turn -= 1.0;
+        turn -= time.delta_secs();
     }
     if input_state.locked_cursor {
         if let Some(axis_data) = input.axis_data(&InputAction::TurnAxis) {
-            accumulated.turn -= axis_data.value * input_settings.mouse_sensitivity;
+            // every unit of mouse movement turns as far as holding a turn key does in a tick
+            turn -= axis_data.value
+                * input_settings.mouse_sensitivity
+                * fixed_time.timestep().as_secs_f32();
         }
     }
+    accumulated.turn += turn * input_settings.turn_rate;
+}
+
+fn handle_input(
+    time: Res<Time>,
+    mut accumulated: ResMut<AccumulatedInput>,
+    input: Res<ActionState<InputAction>>,
+    mut query: Query<(
+        &CharacterController,
+        &mut CharacterControllerState,
+        &mut Player,
+        Option<&mut Dash>,
+    )>,
+) {
+    // the turn is used up by this tick, the movement is held until it changes
+    let turn = std::mem::take(&mut accumulated.turn);
 
-    for (controller, mut physics_state, player) in query.iter_mut() {
+    for (controller, mut physics_state, mut player, dash) in query.iter_mut() {
+        if let Some(ref mut dash) = dash {
+            dash.tick(time.delta());
+        }
         if player.dead {
             physics_state.desired_velocity = Vec3::ZERO;
             continue;
@@ -127,7 +247,18 @@ fn handle_input(
         physics_state.desired_velocity = Quat::from_axis_angle(Vec3::Y, physics_state.heading)
             * (desired_movement * controller.max_speed);
 
-        physics_state.desired_turn = accumulated.turn * input_settings.turn_rate;
+        physics_state.desired_turn = turn;
+
+        let Some(mut dash) = dash else {
+            continue;
+        };
+        if input.just_pressed(&InputAction::Dash) && dash.ready() {
+            // dash forwards when standing still
+            let forward = Quat::from_axis_angle(Vec3::Y, physics_state.heading) * Vec3::NEG_Z;
+            let direction = physics_state.desired_velocity.normalize_or(forward);
+            dash.start(direction);
+            player.grant_invulnerability(Duration::from_secs_f32(dash.invulnerability));
+        }
     }
 }
 
@@ -138,19 +269,29 @@ fn set_velocity(
         &mut CharacterControllerState,
         &mut ReadHeading,
         &mut Velocity,
+        Option<&Dash>,
     )>,
-    mut accumulated: ResMut<AccumulatedInput>,
 ) {
     let dt = time.delta_secs();
 
-    for (controller, mut physics_state, mut read_heading, mut velocity) in query.iter_mut() {
+    for (controller, mut physics_state, mut read_heading, mut velocity, dash) in query.iter_mut() {
         use std::f32::consts::PI;
-        let diff = physics_state.desired_velocity - velocity.linvel;
-        velocity.linvel += diff * controller.acceleration * dt;
+        // only steer the part of the velocity the character is responsible for
+        let mut own_velocity = velocity.linvel - physics_state.impulse;
+        if let Some(dash_velocity) = dash.and_then(Dash::velocity) {
+            own_velocity = dash_velocity;
+        } else {
+            let diff = physics_state.desired_velocity - own_velocity;
+            own_velocity += diff * controller.acceleration * dt;
+        }
 
-        physics_state.heading += physics_state.desired_turn * 2.0 * PI * dt;
-        read_heading.heading = physics_state.heading;
+        physics_state.impulse *= CharacterControllerState::IMPULSE_RETAINED.powf(dt);
+        if physics_state.impulse.length_squared() < 0.01 {
+            physics_state.impulse = Vec3::ZERO;
+        }
+        velocity.linvel = own_velocity + physics_state.impulse;
 
-        accumulated.clear();
+        physics_state.heading += physics_state.desired_turn * 2.0 * PI;
+        read_heading.heading = physics_state.heading;
     }
 }
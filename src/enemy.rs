@@ -0,0 +1,195 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::Collider;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    character_controller::CharacterController,
+    explosion::Falloff,
+    fire_skull::{FireSkull, SkullMovement},
+    health::{DamageType, DeathBehavior, Health, Resistances},
+    pickup::DropTable,
+    projectile::{ProjectileLauncher, RangedAttack},
+    score::KillScore,
+    states::GameState,
+    steering::Steering,
+};
+
+/// The types of enemy. Every kind is a [`FireSkull`] with different stats, see
+/// [`EnemyKind::archetype`].
+#[derive(Component, Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum EnemyKind {
+    #[default]
+    FireSkull,
+    /// Slow, and takes two full shotgun blasts.
+    ArmoredSkull,
+    /// Slow with a huge explosion.
+    BigSkull,
+    /// Small and fast, weaves towards the player, barely explodes.
+    SwiftSkull,
+    /// Keeps its distance and spits fireballs.
+    SpitterSkull,
+}
+
+/// Everything that makes one [`EnemyKind`] different from another.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnemyArchetype {
+    pub health: f32,
+    pub max_speed: f32,
+    pub acceleration: f32,
+    pub movement: SkullMovement,
+    pub steering: Steering,
+    /// Half height and radius of the capsule collider.
+    pub collider: (f32, f32),
+    /// Size of the sprites, relative to a plain fire skull.
+    pub visual_scale: f32,
+    pub death: DeathBehavior,
+    pub resistances: Resistances,
+    /// Points for killing it, before chain and combo bonuses.
+    pub score: u64,
+    pub ranged: Option<RangedAttack>,
+    pub drops: DropTable,
+}
+
+impl EnemyKind {
+    pub fn archetype(self) -> EnemyArchetype {
+        let fire_skull = EnemyArchetype {
+            health: 10.0,
+            max_speed: 5.0,
+            acceleration: 10.0,
+            movement: SkullMovement::Beeline,
+            steering: Steering::default(),
+            collider: (0.5, 0.25),
+            visual_scale: 1.0,
+            death: DeathBehavior::explode(1.0, 25.0),
+            resistances: Resistances::default(),
+            score: 150,
+            ranged: None,
+            drops: DropTable::new(0.08),
+        };
+        match self {
+            EnemyKind::FireSkull => fire_skull,
+            EnemyKind::ArmoredSkull => EnemyArchetype {
+                health: 20.0,
+                max_speed: 4.0,
+                // armored skulls huddle together, shielding each other
+                steering: Steering {
+                    cohesion: 0.6,
+                    ..Steering::default()
+                },
+                // the armour soaks up some of a nearby blast
+                resistances: Resistances {
+                    fire: 0.25,
+                    ..Resistances::default()
+                },
+                score: 300,
+                drops: DropTable::new(0.2),
+                ..fire_skull
+            },
+            EnemyKind::BigSkull => EnemyArchetype {
+                health: 15.0,
+                max_speed: 3.5,
+                acceleration: 5.0,
+                steering: Steering {
+                    separation_radius: 3.0,
+                    ..Steering::default()
+                },
+                collider: (1.0, 0.5),
+                visual_scale: 2.0,
+                // the whole blast is deadly, not just the middle
+                death: DeathBehavior::Explode {
+                    scale: 2.0,
+                    damage: 40.0,
+                    falloff: Falloff::FlatCore { core: 0.5 },
+                    damage_type: DamageType::Fire,
+                },
+                score: 250,
+                drops: DropTable::new(0.15),
+                ..fire_skull
+            },
+            EnemyKind::SwiftSkull => EnemyArchetype {
+                health: 5.0,
+                max_speed: 9.0,
+                acceleration: 20.0,
+                movement: SkullMovement::Weave {
+                    amplitude: 0.6,
+                    frequency: 3.0,
+                },
+                // swarms
+                steering: Steering {
+                    separation: 0.8,
+                    separation_radius: 0.8,
+                    cohesion: 0.6,
+                    ..Steering::default()
+                },
+                collider: (0.25, 0.15),
+                visual_scale: 0.6,
+                death: DeathBehavior::Explode {
+                    scale: 0.5,
+                    damage: 10.0,
+                    falloff: Falloff::Quadratic,
+                    damage_type: DamageType::Fire,
+                },
+                resistances: Resistances::default(),
+                score: 200,
+                ranged: None,
+                // there are too many of them to drop much
+                drops: DropTable::new(0.03),
+            },
+            EnemyKind::SpitterSkull => EnemyArchetype {
+                max_speed: 4.0,
+                movement: SkullMovement::KeepDistance {
+                    min: 12.0,
+                    max: 20.0,
+                },
+                // spread out so they shoot from different directions
+                steering: Steering {
+                    separation: 2.0,
+                    separation_radius: 4.0,
+                    cohesion: 0.0,
+                    ..Steering::default()
+                },
+                visual_scale: 1.2,
+                death: DeathBehavior::explode(0.8, 20.0),
+                // immune to bursting fireballs, their own or other spitters'
+                resistances: Resistances {
+                    magic: 1.0,
+                    ..Resistances::default()
+                },
+                score: 250,
+                ranged: Some(RangedAttack {
+                    interval: 2.5,
+                    projectile_speed: 12.0,
+                    damage: 10.0,
+                }),
+                drops: DropTable::new(0.15),
+                ..fire_skull
+            },
+        }
+    }
+
+    pub fn spawn(self, commands: &mut Commands, pos: Vec3) -> Entity {
+        let archetype = self.archetype();
+        let (half_height, radius) = archetype.collider;
+        let mut entity = commands.spawn((
+            FireSkull::default(),
+            self,
+            Health::new(archetype.health).with_resistances(archetype.resistances),
+            CharacterController {
+                max_speed: archetype.max_speed,
+                acceleration: archetype.acceleration,
+            },
+            Collider::capsule_y(half_height, radius),
+            archetype.movement,
+            archetype.steering,
+            archetype.death,
+            KillScore(archetype.score),
+            archetype.drops,
+            Transform::from_translation(pos),
+            StateScoped(GameState::InGame),
+        ));
+        if let Some(attack) = archetype.ranged {
+            entity.insert(ProjectileLauncher::new(attack));
+        }
+        entity.id()
+    }
+}
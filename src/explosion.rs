@@ -2,11 +2,15 @@ use bevy::prelude::*;
 use bevy_asset_loader::asset_collection::AssetCollection;
 use bevy_rapier3d::prelude::*;
 use bevy_sprite3d::{Sprite3dBuilder, Sprite3dParams};
+use serde::{Deserialize, Serialize};
 
 use crate::{
     assets::AssetLoadingExt,
-    health::{DamageEvent, Health},
-    physics::{ENEMY_GROUP, EXPLOSION_GROUP, PLAYER_GROUP},
+    chain::ChainId,
+    character_controller::CharacterControllerState,
+    health::{DamageEvent, DamageType},
+    physics::{ENEMY_GROUP, EXPLOSION_GROUP, PLAYER_GROUP, PROJECTILE_GROUP, WALL_GROUP},
+    spatial::SpatialIndex,
     sprite::{AnimatedSprite3d, FaceCamera},
     states::{GameState, PauseState},
 };
@@ -17,21 +21,116 @@ pub struct ExplosionPlugin;
 impl Plugin for ExplosionPlugin {
     fn build(&self, app: &mut App) {
         app.add_state_scoped_event::<ExplosionEvent>(GameState::InGame)
-            .load_asset_on_startup::<ExplosionAssets>()
             .add_systems(
-                Update,
-                (spawn_explosion_visual, explosion_collision)
+                FixedUpdate,
+                explosion_collision
                     .run_if(in_state(GameState::InGame).and(in_state(PauseState::Unpaused))),
             );
     }
 }
 
-#[derive(Debug, Event)]
+#[derive(Debug, Default)]
+pub struct ExplosionVisualsPlugin;
+
+impl Plugin for ExplosionVisualsPlugin {
+    fn build(&self, app: &mut App) {
+        app.load_asset_on_startup::<ExplosionAssets>().add_systems(
+            Update,
+            spawn_explosion_visual
+                .run_if(in_state(GameState::InGame).and(in_state(PauseState::Unpaused))),
+        );
+    }
+}
+
+#[derive(Debug, Clone, Copy, Event)]
 pub struct ExplosionEvent {
     pub pos: Vec3,
     pub scale: f32,
+    /// Damage at the centre of the blast.
     pub damage: f32,
+    pub falloff: Falloff,
+    pub damage_type: DamageType,
+    pub occlusion: Occlusion,
+    /// Untouched by the blast, like whoever fired the slug that set it off.
+    pub exclude: Option<Entity>,
     pub chain: u64,
+    pub chain_id: ChainId,
+}
+
+impl ExplosionEvent {
+    /// Blast radius of an explosion with a `scale` of 1.
+    pub const RADIUS: f32 = 2.5;
+
+    pub fn radius(&self) -> f32 {
+        Self::RADIUS * self.scale
+    }
+
+    /// Speed something at the centre of an explosion with a `scale` of 1 gets knocked away at.
+    pub const KNOCKBACK: f32 = 15.0;
+
+    /// Damage dealt to something `dist` metres from the centre.
+    pub fn damage_at(&self, dist: f32) -> f32 {
+        self.damage * self.falloff.sample(dist / self.radius())
+    }
+
+    /// Impulse pushing something at `pos` away from the centre.
+    pub fn knockback_at(&self, pos: Vec3) -> Vec3 {
+        let offset = (pos - self.pos).with_y(0.0);
+        let strength =
+            Self::KNOCKBACK * self.scale * self.falloff.sample(offset.length() / self.radius());
+        offset.normalize_or_zero() * strength
+    }
+}
+
+/// How an explosion's damage drops off towards the edge of its radius.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Falloff {
+    #[default]
+    Linear,
+    /// Drops off quickly, so only things near the centre take much damage.
+    Quadratic,
+    /// Full damage inside `core` (a fraction of the radius), then linear to the edge.
+    FlatCore { core: f32 },
+}
+
+/// What happens to things on the other side of a wall from an explosion.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Occlusion {
+    /// Walls don't matter.
+    Ignore,
+    /// Nothing gets through.
+    #[default]
+    Block,
+    /// Damage and knockback are multiplied by this.
+    Reduce(f32),
+}
+
+impl Occlusion {
+    fn multiplier(self) -> f32 {
+        match self {
+            Occlusion::Ignore => 1.0,
+            Occlusion::Block => 0.0,
+            Occlusion::Reduce(multiplier) => multiplier,
+        }
+    }
+}
+
+impl Falloff {
+    /// Damage multiplier at `t`, the distance from the centre as a fraction of the radius.
+    pub fn sample(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Falloff::Linear => 1.0 - t,
+            Falloff::Quadratic => (1.0 - t) * (1.0 - t),
+            Falloff::FlatCore { core } => {
+                if t <= core {
+                    1.0
+                } else {
+                    (1.0 - t) / (1.0 - core).max(f32::EPSILON)
+                }
+            }
+        }
+    }
 }
 
 #[derive(Debug, Resource, AssetCollection)]
@@ -44,9 +143,10 @@ struct ExplosionAssets {
 
 fn explosion_collision(
     mut reader: EventReader<ExplosionEvent>,
+    index: Res<SpatialIndex>,
     read_rapier_context: ReadRapierContext,
     mut writer: EventWriter<DamageEvent>,
-    query: Query<&GlobalTransform, With<Health>>,
+    mut controller_query: Query<&mut CharacterControllerState>,
 ) {
     if reader.is_empty() {
         return;
@@ -55,35 +155,56 @@ fn explosion_collision(
         error!("Failed to get rapier context");
         return;
     };
-    let filter = QueryFilter::new().groups(CollisionGroups {
+    let wall_filter = QueryFilter::new().groups(CollisionGroups {
         memberships: EXPLOSION_GROUP,
-        filters: ENEMY_GROUP | PLAYER_GROUP,
+        filters: WALL_GROUP,
     });
-    for ExplosionEvent {
-        pos,
-        scale,
-        damage,
-        chain,
-    } in reader.read()
-    {
-        let radius = 2.5 * scale;
-        let shape = Collider::ball(radius);
-        context.intersections_with_shape(*pos, Quat::IDENTITY, &shape, filter, |entity| {
-            if let Ok(global_transform) = query.get(entity) {
-                let dist = global_transform.translation().distance(*pos);
-                let damage = damage * (1.0 - dist / radius).max(0.0);
-
-                info!("explosion hit entity {}, dealing {} damage", entity, damage);
-                writer.write(DamageEvent {
-                    entity,
-                    damage,
-                    chain: *chain,
-                });
+    for explosion in reader.read() {
+        // skulls roam outside the arena, inside the wall colliders, and a blast that starts inside
+        // a wall would otherwise be blocked by the wall it's in
+        let inside_wall = context
+            .project_point(explosion.pos, true, wall_filter)
+            .is_some_and(|(_, projection)| projection.is_inside);
+        let groups = ENEMY_GROUP | PLAYER_GROUP | PROJECTILE_GROUP;
+        for hit in index.within(explosion.pos, explosion.radius(), groups) {
+            if explosion.exclude == Some(hit.entity) {
+                continue;
+            }
+            let offset = hit.pos - explosion.pos;
+            let occluded = explosion.occlusion != Occlusion::Ignore
+                && !inside_wall
+                && context
+                    .cast_ray(explosion.pos, offset, 1.0, true, wall_filter)
+                    .is_some();
+            let multiplier = if occluded {
+                explosion.occlusion.multiplier()
+            } else {
+                1.0
+            };
+            if multiplier <= 0.0 {
+                continue;
             }
 
-            // return true to keep searching
-            true
-        });
+            let damage = explosion.damage_at(offset.length()) * multiplier;
+
+            trace!(
+                "explosion hit entity {}, dealing {} damage",
+                hit.entity, damage
+            );
+            writer.write(DamageEvent {
+                entity: hit.entity,
+                damage,
+                damage_type: explosion.damage_type,
+                chain: explosion.chain,
+                chain_id: explosion.chain_id,
+            });
+
+            if hit.groups.intersects(ENEMY_GROUP | PLAYER_GROUP) {
+                if let Ok(mut state) = controller_query.get_mut(hit.entity) {
+                    state.apply_impulse(explosion.knockback_at(hit.pos) * multiplier);
+                }
+            }
+        }
     }
 }
 
@@ -125,3 +246,52 @@ fn spawn_explosion_visual(
         ));
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: [Falloff; 4] = [
+        Falloff::Linear,
+        Falloff::Quadratic,
+        Falloff::FlatCore { core: 0.3 },
+        Falloff::FlatCore { core: 1.0 },
+    ];
+
+    #[test]
+    fn full_damage_at_the_centre() {
+        for falloff in ALL {
+            assert_eq!(falloff.sample(0.0), 1.0, "{falloff:?}");
+            // closer than the centre isn't a thing
+            assert_eq!(falloff.sample(-1.0), 1.0, "{falloff:?}");
+        }
+    }
+
+    #[test]
+    fn nothing_at_the_edge() {
+        for falloff in [
+            Falloff::Linear,
+            Falloff::Quadratic,
+            Falloff::FlatCore { core: 0.3 },
+        ] {
+            assert_eq!(falloff.sample(1.0), 0.0, "{falloff:?}");
+            assert_eq!(falloff.sample(2.0), 0.0, "{falloff:?}");
+        }
+    }
+
+    #[test]
+    fn halfway() {
+        assert_eq!(Falloff::Linear.sample(0.5), 0.5);
+        assert_eq!(Falloff::Quadratic.sample(0.5), 0.25);
+        assert_eq!(Falloff::FlatCore { core: 0.5 }.sample(0.5), 1.0);
+        assert!((Falloff::FlatCore { core: 0.5 }.sample(0.75) - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn a_core_covering_everything_is_flat() {
+        // no dividing by zero at the edge
+        let falloff = Falloff::FlatCore { core: 1.0 };
+        assert_eq!(falloff.sample(1.0), 1.0);
+        assert_eq!(falloff.sample(2.0), 1.0);
+    }
+}
@@ -7,12 +7,16 @@ use bevy_sprite3d::prelude::*;
 
 use crate::{
     assets::AssetLoadingExt,
+    chain::{ChainRoot, ChainTracker},
     character_controller::{CharacterController, CharacterControllerState},
-    health::{DamageEvent, Health},
-    physics::{ENEMY_GROUP, EXPLOSION_GROUP, PLAYER_GROUP, SHOTGUN_GROUP},
+    enemy::EnemyKind,
+    health::{DamageEvent, DamageType, DeathBehavior, Fuse, Health},
+    physics::{ENEMY_GROUP, EXPLOSION_GROUP, PLAYER_GROUP, WEAPON_GROUP},
     player::Player,
+    score::KillScore,
     sprite::{AnimatedSprite3d, FaceCamera},
     states::{GameState, PauseState},
+    steering::{Steering, SteeringForce, SteeringSet},
 };
 
 #[derive(Debug, Default)]
@@ -21,30 +25,58 @@ pub struct FireSkullPlugin;
 impl Plugin for FireSkullPlugin {
     fn build(&self, app: &mut App) {
         app.add_state_scoped_event::<FireSkullEvent>(GameState::InGame)
-            .load_asset_on_startup::<FireSkullAssets>()
             .add_systems(
-                Update,
-                (
-                    spawn_fire_skull_visuals,
-                    bobbing_animation,
-                    fire_skull_collision,
-                    move_skulls.in_set(bevy_rapier3d::plugin::PhysicsSet::SyncBackend),
-                )
+                FixedUpdate,
+                (fire_skull_collision, move_skulls.after(SteeringSet))
                     .run_if(in_state(GameState::InGame).and(in_state(PauseState::Unpaused))),
             );
     }
 }
 
+#[derive(Debug, Default)]
+pub struct FireSkullVisualsPlugin;
+
+impl Plugin for FireSkullVisualsPlugin {
+    fn build(&self, app: &mut App) {
+        app.load_asset_on_startup::<FireSkullAssets>().add_systems(
+            Update,
+            (
+                spawn_fire_skull_visuals,
+                bobbing_animation,
+                primed_skull_visuals,
+            )
+                .run_if(in_state(GameState::InGame).and(in_state(PauseState::Unpaused))),
+        );
+    }
+}
+
 #[derive(Debug, Default, Component)]
 #[require(
     Visibility,
     Health::new(10.0),
     CharacterController = CharacterController { max_speed: 5.0, acceleration: 10.0 },
-    CollisionGroups::new(ENEMY_GROUP, PLAYER_GROUP | ENEMY_GROUP | SHOTGUN_GROUP | EXPLOSION_GROUP),
+    CollisionGroups::new(ENEMY_GROUP, PLAYER_GROUP | ENEMY_GROUP | WEAPON_GROUP | EXPLOSION_GROUP),
     Collider::capsule_y(0.5, 0.25),
+    EnemyKind,
+    SkullMovement,
+    Steering,
+    DeathBehavior = DeathBehavior::explode(1.0, 25.0),
+    KillScore = KillScore(150),
 )]
 pub struct FireSkull {}
 
+/// How a skull gets to the player.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Component)]
+pub enum SkullMovement {
+    /// Straight at them.
+    #[default]
+    Beeline,
+    /// Swerving from side to side on the way.
+    Weave { amplitude: f32, frequency: f32 },
+    /// Stays between `min` and `max` metres away.
+    KeepDistance { min: f32, max: f32 },
+}
+
 #[derive(Debug, Default, Component)]
 #[require(Transform, Visibility)]
 struct FireSkullVisualRoot {
@@ -72,17 +104,19 @@ fn bobbing_animation(
 fn spawn_fire_skull_visuals(
     mut commands: Commands,
     visuals: Res<FireSkullAssets>,
-    query: Query<Entity, Added<FireSkull>>,
+    query: Query<(Entity, &EnemyKind), Added<FireSkull>>,
     mut sprite3d_params: Sprite3dParams,
     mut animation_offset: Local<f32>,
 ) {
-    for entity in query.iter() {
+    for (entity, kind) in query.iter() {
+        let scale = kind.archetype().visual_scale;
         let visual_root = commands
             .spawn((
                 FireSkullVisualRoot {
                     t: *animation_offset,
                 },
                 FaceCamera::default(),
+                Transform::from_scale(Vec3::splat(scale)),
             ))
             .with_children(|s| {
                 let atlas = TextureAtlas {
@@ -146,6 +180,30 @@ fn spawn_fire_skull_visuals(
     }
 }
 
+/// Chatters the skull's teeth and swells it up while its [`Fuse`] burns.
+fn primed_skull_visuals(
+    fuse_query: Query<(&Fuse, &EnemyKind)>,
+    mut root_query: Query<(&ChildOf, &mut Transform), With<FireSkullVisualRoot>>,
+    mut skull_query: Query<(&ChildOf, &mut AnimatedSprite3d), With<FireSkullSkullVisual>>,
+) {
+    const CHATTER_INTERVAL: Duration = Duration::from_millis(50);
+    const SWELL: f32 = 0.4;
+    for (&ChildOf(parent), mut transform) in root_query.iter_mut() {
+        if let Ok((fuse, kind)) = fuse_query.get(parent) {
+            let scale = kind.archetype().visual_scale * (1.0 + SWELL * fuse.timer.fraction());
+            transform.scale = Vec3::splat(scale);
+        }
+    }
+    for (&ChildOf(root), mut animation) in skull_query.iter_mut() {
+        let Ok((&ChildOf(parent), _)) = root_query.get(root) else {
+            continue;
+        };
+        if fuse_query.contains(parent) && animation.timer.duration() != CHATTER_INTERVAL {
+            animation.timer.set_duration(CHATTER_INTERVAL);
+        }
+    }
+}
+
 #[derive(Resource, AssetCollection)]
 struct FireSkullAssets {
     #[asset(path = "textures/skull_atlas.png")]
@@ -172,45 +230,94 @@ impl FireSkullAssets {
 pub enum FireSkullEvent {}
 
 fn move_skulls(
+    time: Res<Time>,
     player_transform: Single<&GlobalTransform, With<crate::player::Player>>,
     mut skull_query: Query<
         (
+            Entity,
             &GlobalTransform,
             &CharacterController,
+            &SkullMovement,
+            &Steering,
+            &SteeringForce,
+            Has<Fuse>,
             &mut CharacterControllerState,
         ),
         (With<FireSkull>, Without<crate::player::Player>),
     >,
 ) {
     let player_pos = player_transform.translation();
-    for (skull_transform, controller, mut state) in skull_query.iter_mut() {
-        let dir = (player_pos - skull_transform.translation()).normalize_or_zero();
+    for (entity, skull_transform, controller, movement, steering, force, primed, mut state) in
+        skull_query.iter_mut()
+    {
+        if primed {
+            state.desired_velocity = Vec3::ZERO;
+            continue;
+        }
+        let to_player = player_pos - skull_transform.translation();
+        let mut dir = to_player.normalize_or_zero();
+        match *movement {
+            SkullMovement::Beeline => {}
+            SkullMovement::Weave {
+                amplitude,
+                frequency,
+            } => {
+                // offset each skull so they don't all swerve in step
+                let phase = entity.index() as f32;
+                let side = Vec3::new(-dir.z, 0.0, dir.x);
+                dir = (dir + side * amplitude * f32::sin(time.elapsed_secs() * frequency + phase))
+                    .normalize_or_zero();
+            }
+            SkullMovement::KeepDistance { min, max } => {
+                let dist = to_player.length();
+                if dist < min {
+                    dir = -dir;
+                } else if dist <= max {
+                    dir = Vec3::ZERO;
+                }
+            }
+        }
 
+        // don't let the extra forces make it faster than it's allowed to go
+        let dir = (dir * steering.seek + force.0).clamp_length_max(1.0);
         state.desired_velocity = dir * controller.max_speed;
     }
 }
 
 fn fire_skull_collision(
+    time: Res<Time>,
     mut writer: EventWriter<DamageEvent>,
     mut collisions: EventReader<CollisionEvent>,
-    skull_query: Query<&FireSkull>,
+    mut chains: ResMut<ChainTracker>,
+    skull_query: Query<&GlobalTransform, (With<FireSkull>, Without<Fuse>)>,
     player_query: Query<&Player>,
 ) {
     for ev in collisions.read() {
         if let &CollisionEvent::Started(e1, e2, _flags) = ev {
-            if let (Ok(_player), Ok(_skull)) = (player_query.get(e1), skull_query.get(e2)) {
-                writer.write(DamageEvent {
-                    entity: e2,
-                    damage: f32::INFINITY,
-                    chain: 0,
-                });
-            } else if let (Ok(_player), Ok(_skull)) = (player_query.get(e2), skull_query.get(e1)) {
-                writer.write(DamageEvent {
-                    entity: e1,
-                    damage: f32::INFINITY,
-                    chain: 0,
-                });
-            }
+            let skull = if player_query.contains(e1) {
+                e2
+            } else if player_query.contains(e2) {
+                e1
+            } else {
+                continue;
+            };
+            let Ok(skull_transform) = skull_query.get(skull) else {
+                continue;
+            };
+
+            let chain_id = chains.start(
+                ChainRoot::Contact {
+                    pos: skull_transform.translation(),
+                },
+                time.elapsed(),
+            );
+            writer.write(DamageEvent {
+                entity: skull,
+                damage: f32::INFINITY,
+                damage_type: DamageType::Kinetic,
+                chain: 0,
+                chain_id,
+            });
         }
     }
 }
@@ -0,0 +1,44 @@
+use std::fmt;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Which rules the next (or current) run is played with. Picked on the main menu.
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[repr(u8)]
+pub enum GameMode {
+    /// Spawners keep appearing faster and faster until the player dies.
+    #[default]
+    Endless = 0,
+    /// Fixed waves with a break in between.
+    Waves = 1,
+}
+
+impl GameMode {
+    pub const ALL: [GameMode; 2] = [GameMode::Endless, GameMode::Waves];
+}
+
+impl fmt::Display for GameMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GameMode::Endless => write!(f, "Endless"),
+            GameMode::Waves => write!(f, "Waves"),
+        }
+    }
+}
+
+impl TryFrom<u8> for GameMode {
+    type Error = u8;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        GameMode::ALL
+            .into_iter()
+            .find(|mode| *mode as u8 == value)
+            .ok_or(value)
+    }
+}
+
+/// Run condition that's true while `mode` is selected.
+pub fn in_game_mode(mode: GameMode) -> impl FnMut(Res<GameMode>) -> bool + Clone {
+    move |current: Res<GameMode>| *current == mode
+}
@@ -0,0 +1,228 @@
+//! Runs the game loop without a window, renderer or audio backend.
+//!
+//! Every call to [`HeadlessAppExt::step_ticks`] advances time by exactly one fixed timestep per
+//! tick, so the gameplay systems and physics in `FixedUpdate` run exactly once per update, and a
+//! run is reproducible from its inputs alone. Useful for integration tests of chain reactions,
+//! scoring and spawning.
+
+use std::time::{Duration, Instant};
+
+use bevy::{prelude::*, time::TimeUpdateStrategy};
+use leafwing_input_manager::prelude::ActionState;
+
+use crate::{
+    GameplayPlugin,
+    chain::{ChainId, ChainRoot, ChainTracker},
+    enemy::EnemyKind,
+    health::{DamageEvent, DamageType},
+    input::{InputAction, InputSettings, InputState},
+    rand::{NextRunSeed, RunSeed},
+    spawner::SkullsKilled,
+    states::GameState,
+};
+
+/// Length of a single simulation tick, matching Bevy's default fixed timestep.
+pub fn tick_duration() -> Duration {
+    Time::<Fixed>::default().timestep()
+}
+
+/// Builds an [`App`] with all of the gameplay plugins and none of the presentation ones.
+///
+/// The returned app is sitting in [`GameState::MainMenu`]; call
+/// [`HeadlessAppExt::start_game`] to begin a run.
+pub fn headless_app() -> App {
+    let tick = tick_duration();
+
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        TransformPlugin,
+        bevy::asset::AssetPlugin::default(),
+        bevy::scene::ScenePlugin,
+        bevy::state::app::StatesPlugin,
+    ))
+    // rapier's async colliders need mesh assets to exist, even if nothing uses them
+    .init_asset::<Mesh>()
+    .add_plugins(crate::states::StatesPlugin)
+    // normally provided by `InputPlugin`, which needs a window. Actions can be pressed
+    // directly on the `ActionState` resource instead.
+    .init_resource::<ActionState<InputAction>>()
+    .init_resource::<InputSettings>()
+    .init_resource::<InputState>()
+    .add_plugins(GameplayPlugin)
+    .insert_resource(TimeUpdateStrategy::ManualDuration(tick));
+
+    app.finish();
+    app.cleanup();
+
+    // run startup systems and get out of `GameState::Startup`
+    app.world_mut()
+        .resource_mut::<NextState<GameState>>()
+        .set(GameState::MainMenu);
+    app.update();
+
+    app
+}
+
+pub trait HeadlessAppExt {
+    /// Enters [`GameState::InGame`] and runs the tick that spawns the player and first spawner.
+    fn start_game(&mut self) -> &mut Self;
+
+    /// Like [`HeadlessAppExt::start_game`], but with a fixed [`RunSeed`] so the run is reproducible.
+    fn start_game_with_seed(&mut self, seed: u64) -> &mut Self;
+
+    /// Advances the simulation by `ticks` fixed timesteps.
+    fn step_ticks(&mut self, ticks: u32) -> &mut Self;
+
+    /// Advances the simulation by at least `seconds` of game time.
+    fn step_seconds(&mut self, seconds: f32) -> &mut Self;
+
+    /// Spawns a `kind` enemy at each of `positions`, and steps until their colliders and the
+    /// spatial index have caught up.
+    fn spawn_settled(
+        &mut self,
+        kind: EnemyKind,
+        positions: impl IntoIterator<Item = Vec3>,
+    ) -> Vec<Entity>;
+
+    /// Starts tracking a new chain reaction, as if `root` had just happened.
+    fn start_chain(&mut self, root: ChainRoot) -> ChainId;
+
+    /// Kills `entity` outright, as if the player had shot it, at the root of a new chain.
+    fn detonate(&mut self, entity: Entity) -> ChainId;
+}
+
+impl HeadlessAppExt for App {
+    fn start_game(&mut self) -> &mut Self {
+        self.world_mut()
+            .resource_mut::<NextState<GameState>>()
+            .set(GameState::InGame);
+        self.step_ticks(1)
+    }
+
+    fn start_game_with_seed(&mut self, seed: u64) -> &mut Self {
+        self.insert_resource(NextRunSeed(Some(RunSeed(seed))));
+        self.start_game()
+    }
+
+    fn step_ticks(&mut self, ticks: u32) -> &mut Self {
+        for _ in 0..ticks {
+            self.update();
+        }
+        self
+    }
+
+    fn step_seconds(&mut self, seconds: f32) -> &mut Self {
+        let ticks = (seconds / tick_duration().as_secs_f32()).ceil() as u32;
+        self.step_ticks(ticks)
+    }
+
+    fn spawn_settled(
+        &mut self,
+        kind: EnemyKind,
+        positions: impl IntoIterator<Item = Vec3>,
+    ) -> Vec<Entity> {
+        let entities: Vec<_> = {
+            let mut commands = self.world_mut().commands();
+            positions
+                .into_iter()
+                .map(|pos| kind.spawn(&mut commands, pos))
+                .collect()
+        };
+        self.world_mut().flush();
+        self.step_ticks(2);
+        entities
+    }
+
+    fn start_chain(&mut self, root: ChainRoot) -> ChainId {
+        let now = self.world().resource::<Time>().elapsed();
+        self.world_mut()
+            .resource_mut::<ChainTracker>()
+            .start(root, now)
+    }
+
+    fn detonate(&mut self, entity: Entity) -> ChainId {
+        let chain_id = self.start_chain(ChainRoot::Shot { origin: Vec3::ZERO });
+        self.world_mut().send_event(DamageEvent {
+            entity,
+            damage: f32::INFINITY,
+            damage_type: DamageType::Kinetic,
+            chain: 0,
+            chain_id,
+        });
+        chain_id
+    }
+}
+
+/// Frame timings from [`chain_stress`].
+#[derive(Debug, Clone)]
+pub struct StressReport {
+    pub skulls: usize,
+    pub kills: usize,
+    /// Wall clock time of every tick from the first damage until the chain settled.
+    pub frame_times: Vec<Duration>,
+}
+
+impl StressReport {
+    pub fn total(&self) -> Duration {
+        self.frame_times.iter().sum()
+    }
+
+    pub fn mean(&self) -> Duration {
+        self.total() / self.frame_times.len().max(1) as u32
+    }
+
+    pub fn worst(&self) -> Duration {
+        self.frame_times.iter().copied().max().unwrap_or_default()
+    }
+}
+
+/// Packs `skulls` fire skulls into a grid tight enough for every explosion to reach its
+/// neighbours, blows up one corner and times each tick until the chain settles.
+pub fn chain_stress(skulls: usize, seed: u64) -> StressReport {
+    // close enough that a skull's explosion kills its neighbours
+    const SPACING: f32 = 1.2;
+    // give up on chains that never settle
+    const MAX_TICKS: u32 = 600;
+
+    let mut app = headless_app();
+    app.start_game_with_seed(seed);
+
+    let columns = (skulls as f32).sqrt().ceil().max(1.0) as usize;
+    // keep the grid in front of the player, away from its explosions
+    let origin = Vec3::new(-0.5 * SPACING * columns as f32, 0.0, -8.0);
+    let entities = app.spawn_settled(
+        EnemyKind::FireSkull,
+        (0..skulls).map(|i| {
+            origin + Vec3::new((i % columns) as f32, 0.0, -((i / columns) as f32)) * SPACING
+        }),
+    );
+
+    let kills_before = app.world().resource::<SkullsKilled>().count;
+    if let Some(&first) = entities.first() {
+        app.detonate(first);
+    }
+
+    let mut frame_times = Vec::new();
+    for _ in 0..MAX_TICKS {
+        let start = Instant::now();
+        app.update();
+        frame_times.push(start.elapsed());
+
+        if app
+            .world()
+            .resource::<ChainTracker>()
+            .active()
+            .next()
+            .is_none()
+        {
+            break;
+        }
+    }
+
+    StressReport {
+        skulls,
+        kills: app.world().resource::<SkullsKilled>().count - kills_before,
+        frame_times,
+    }
+}
@@ -1,10 +1,15 @@
+use std::time::Duration;
+
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    explosion::ExplosionEvent,
-    fire_skull::FireSkull,
+    chain::ChainId,
+    explosion::{ExplosionEvent, Falloff, Occlusion},
+    pickup::{BigExplosions, DropEvent, DropTable},
     player::{Player, PlayerHurtEvent},
-    score::ScoreEvent,
+    score::{KillScore, ScoreEvent},
+    shop::Upgrades,
     spawner::SkullsKilled,
     states::{GameState, PauseState},
 };
@@ -14,10 +19,13 @@ pub struct HealthPlugin;
 
 impl Plugin for HealthPlugin {
     fn build(&self, app: &mut App) {
-        app.add_state_scoped_event::<DamageEvent>(GameState::InGame)
+        app.init_resource::<FuseSettings>()
+            .add_state_scoped_event::<DamageEvent>(GameState::InGame)
+            .add_state_scoped_event::<FuseLitEvent>(GameState::InGame)
             .add_systems(
-                Update,
-                handle_damage
+                FixedUpdate,
+                (handle_damage, burn_fuses)
+                    .chain()
                     .run_if(in_state(GameState::InGame).and(in_state(PauseState::Unpaused))),
             );
     }
@@ -26,53 +34,190 @@ impl Plugin for HealthPlugin {
 #[derive(Debug, Component)]
 pub struct Health {
     pub current: f32,
+    /// Healing stops here.
+    pub max: f32,
     pub dead: bool,
+    pub resistances: Resistances,
 }
 
 impl Health {
     pub fn new(health: f32) -> Self {
         Health {
             current: health,
+            max: health,
             dead: health <= 0.0,
+            resistances: Resistances::default(),
+        }
+    }
+
+    pub fn with_resistances(mut self, resistances: Resistances) -> Self {
+        self.resistances = resistances;
+        self
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DamageType {
+    /// Skull explosions.
+    #[default]
+    Fire,
+    /// Fireball bursts.
+    Magic,
+    /// Bullets and being run into.
+    Kinetic,
+}
+
+/// The fraction of each [`DamageType`] that's ignored. `1.0` is immune, negative values are
+/// weaknesses.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Resistances {
+    pub fire: f32,
+    pub magic: f32,
+    pub kinetic: f32,
+}
+
+impl Resistances {
+    pub fn get(&self, damage_type: DamageType) -> f32 {
+        match damage_type {
+            DamageType::Fire => self.fire,
+            DamageType::Magic => self.magic,
+            DamageType::Kinetic => self.kinetic,
+        }
+    }
+
+    /// How much of `damage` gets through.
+    pub fn apply(&self, damage: f32, damage_type: DamageType) -> f32 {
+        let multiplier = 1.0 - self.get(damage_type);
+        // don't turn infinite damage into NaN for immune targets
+        if multiplier <= 0.0 {
+            0.0
+        } else {
+            damage * multiplier
+        }
+    }
+}
+
+/// What happens to something (other than the player) when its [`Health`] runs out.
+#[derive(Debug, Clone, Copy, PartialEq, Component)]
+pub enum DeathBehavior {
+    /// Just disappear.
+    Despawn,
+    /// Blow up, damaging everything nearby and continuing the chain.
+    Explode {
+        scale: f32,
+        damage: f32,
+        falloff: Falloff,
+        damage_type: DamageType,
+    },
+}
+
+impl DeathBehavior {
+    /// A fire explosion with linear falloff.
+    pub const fn explode(scale: f32, damage: f32) -> Self {
+        DeathBehavior::Explode {
+            scale,
+            damage,
+            falloff: Falloff::Linear,
+            damage_type: DamageType::Fire,
+        }
+    }
+}
+
+/// How long something killed by a chain reaction waits before exploding. Anything killed
+/// directly (chain 0) explodes straight away.
+#[derive(Debug, Clone, Copy, PartialEq, Resource)]
+pub struct FuseSettings {
+    /// Fuse length for the first link of a chain, in seconds.
+    pub delay: f32,
+    /// Multiplies the fuse length for every link after the first, so long chains speed up.
+    pub depth_scale: f32,
+    pub min_delay: f32,
+}
+
+impl Default for FuseSettings {
+    fn default() -> Self {
+        FuseSettings {
+            delay: 0.2,
+            depth_scale: 0.9,
+            min_delay: 0.05,
+        }
+    }
+}
+
+impl FuseSettings {
+    pub fn delay(&self, chain: u64) -> Duration {
+        if chain == 0 {
+            return Duration::ZERO;
         }
+        let links = (chain - 1).min(i32::MAX as u64) as i32;
+        let delay = self.delay * self.depth_scale.powi(links);
+        Duration::from_secs_f32(delay.max(self.min_delay))
     }
 }
 
+/// Something that died and is about to explode. It no longer moves or takes damage.
+#[derive(Debug, Component)]
+pub struct Fuse {
+    pub timer: Timer,
+    pub explosion: ExplosionEvent,
+}
+
+/// Sent when a [`Fuse`] starts burning.
+#[derive(Debug, Clone, Copy, Event)]
+pub struct FuseLitEvent {
+    pub entity: Entity,
+    pub pos: Vec3,
+}
+
 #[derive(Debug, Clone, Copy, Event)]
 pub struct DamageEvent {
     pub entity: Entity,
     pub damage: f32,
+    pub damage_type: DamageType,
     pub chain: u64,
+    pub chain_id: ChainId,
 }
 
 fn handle_damage(
     mut commands: Commands,
     mut reader: EventReader<DamageEvent>,
     mut explosion_writer: EventWriter<ExplosionEvent>,
+    mut fuse_writer: EventWriter<FuseLitEvent>,
     mut player_hurt_writer: EventWriter<PlayerHurtEvent>,
     mut score_writer: EventWriter<ScoreEvent>,
+    mut drop_writer: EventWriter<DropEvent>,
     mut kill_count: ResMut<SkullsKilled>,
+    fuse_settings: Res<FuseSettings>,
+    upgrades: Res<Upgrades>,
+    big_explosions: Query<(), With<BigExplosions>>,
     mut query: Query<(
         &mut Health,
         &GlobalTransform,
         Option<&mut Player>,
-        Option<&FireSkull>,
+        Option<&DeathBehavior>,
+        Option<&KillScore>,
+        Option<&DropTable>,
     )>,
 ) {
     for DamageEvent {
         entity,
         damage,
+        damage_type,
         chain,
+        chain_id,
     } in reader.read()
     {
-        let Ok((mut health, global_transform, mut player, skull)) = query.get_mut(*entity) else {
+        let Ok((mut health, global_transform, mut player, death, kill_score, drops)) =
+            query.get_mut(*entity)
+        else {
             continue;
         };
+        let damage = health.resistances.apply(*damage, *damage_type);
 
         if let Some(ref mut player) = player {
-            if player.is_vulnerable() && *damage > 0.0 {
+            if player.is_vulnerable() && damage > 0.0 {
                 health.current -= damage;
-                player.invulnerability_timer.reset();
+                player.grant_invulnerability(Player::HURT_INVULNERABILITY);
                 player_hurt_writer.write(PlayerHurtEvent {});
             }
         } else {
@@ -82,21 +227,147 @@ fn handle_damage(
         if health.current <= 0.0 && !health.dead {
             health.dead = true;
             if player.is_some() {
-                info!("player died, do something");
+                info!("player died");
             }
-            if skull.is_some() {
-                if let Ok(mut c) = commands.get_entity(*entity) {
+            let Some(death) = death else {
+                continue;
+            };
+            let Ok(mut c) = commands.get_entity(*entity) else {
+                continue;
+            };
+            let pos = global_transform.translation();
+            match *death {
+                DeathBehavior::Despawn => {
                     c.despawn();
-                    explosion_writer.write(ExplosionEvent {
-                        pos: global_transform.translation(),
-                        scale: 1.0,
-                        damage: 25.0,
+                }
+                DeathBehavior::Explode {
+                    scale,
+                    damage: explosion_damage,
+                    falloff,
+                    damage_type: explosion_damage_type,
+                } => {
+                    let mut scale = scale * upgrades.explosion_scale();
+                    if !big_explosions.is_empty() {
+                        scale *= BigExplosions::SCALE;
+                    }
+                    let explosion = ExplosionEvent {
+                        pos,
+                        scale,
+                        damage: explosion_damage,
+                        falloff,
+                        damage_type: explosion_damage_type,
+                        occlusion: Occlusion::default(),
+                        exclude: None,
                         chain: *chain + 1,
-                    });
-                    kill_count.count += 1;
-                    score_writer.write(ScoreEvent { chain: *chain });
+                        chain_id: *chain_id,
+                    };
+                    let delay = fuse_settings.delay(*chain);
+                    if delay.is_zero() {
+                        c.despawn();
+                        explosion_writer.write(explosion);
+                    } else {
+                        c.insert(Fuse {
+                            timer: Timer::new(delay, TimerMode::Once),
+                            explosion,
+                        });
+                        fuse_writer.write(FuseLitEvent {
+                            entity: *entity,
+                            pos,
+                        });
+                    }
                 }
             }
+            if let Some(kill_score) = kill_score {
+                kill_count.count += 1;
+                score_writer.write(ScoreEvent {
+                    pos,
+                    value: kill_score.0,
+                    chain: *chain,
+                    chain_id: *chain_id,
+                });
+            }
+            if let Some(drops) = drops {
+                drop_writer.write(DropEvent {
+                    pos,
+                    table: drops.clone(),
+                });
+            }
         }
     }
 }
+
+fn burn_fuses(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut writer: EventWriter<ExplosionEvent>,
+    mut query: Query<(Entity, &GlobalTransform, &mut Fuse)>,
+) {
+    for (entity, global_transform, mut fuse) in query.iter_mut() {
+        if !fuse.timer.tick(time.delta()).just_finished() {
+            continue;
+        }
+        writer.write(ExplosionEvent {
+            // it may have been pushed around while the fuse burned
+            pos: global_transform.translation(),
+            ..fuse.explosion
+        });
+        commands.entity(entity).despawn();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_secs(duration: Duration, expected: f32) {
+        assert!(
+            (duration.as_secs_f32() - expected).abs() < 1e-6,
+            "{duration:?} isn't {expected}s"
+        );
+    }
+
+    #[test]
+    fn resistances_scale_damage() {
+        let resistances = Resistances {
+            fire: 0.25,
+            magic: -0.5,
+            kinetic: 0.0,
+        };
+        assert_eq!(resistances.apply(8.0, DamageType::Fire), 6.0);
+        // weaknesses take extra
+        assert_eq!(resistances.apply(8.0, DamageType::Magic), 12.0);
+        assert_eq!(resistances.apply(8.0, DamageType::Kinetic), 8.0);
+    }
+
+    #[test]
+    fn immunity_blocks_even_infinite_damage() {
+        let resistances = Resistances {
+            fire: 1.0,
+            magic: 2.0,
+            kinetic: 0.0,
+        };
+        assert_eq!(resistances.apply(f32::INFINITY, DamageType::Fire), 0.0);
+        assert_eq!(resistances.apply(10.0, DamageType::Magic), 0.0);
+        assert_eq!(
+            resistances.apply(f32::INFINITY, DamageType::Kinetic),
+            f32::INFINITY
+        );
+    }
+
+    #[test]
+    fn fuses_shorten_down_the_chain() {
+        let fuse = FuseSettings::default();
+        // whatever was shot goes off straight away
+        assert_eq!(fuse.delay(0), Duration::ZERO);
+        assert_secs(fuse.delay(1), 0.2);
+        assert_secs(fuse.delay(2), 0.18);
+        assert_secs(fuse.delay(3), 0.162);
+    }
+
+    #[test]
+    fn fuses_never_get_shorter_than_the_minimum() {
+        let fuse = FuseSettings::default();
+        assert_secs(fuse.delay(100), 0.05);
+        assert_secs(fuse.delay(u64::MAX), 0.05);
+    }
+}
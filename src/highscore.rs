@@ -0,0 +1,126 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    rand::RunSeed,
+    replay::Recording,
+    score::{RunStats, Score},
+    spawner::SkullsKilled,
+    states::GameState,
+    storage,
+};
+
+#[derive(Debug, Default)]
+pub struct HighScorePlugin;
+
+impl Plugin for HighScorePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(HighScores::load())
+            .add_systems(
+                OnEnter(GameState::InGame),
+                |mut high_scores: ResMut<HighScores>| high_scores.last_run = None,
+            )
+            .add_systems(
+                OnEnter(GameState::End),
+                // `Recording` only exists after a run that was actually played, so watching
+                // a replay doesn't submit its score a second time
+                submit_run
+                    .in_set(SubmitHighScore)
+                    .run_if(resource_exists::<Recording>),
+            );
+    }
+}
+
+/// Adds the finished run to [`HighScores`]. Anything showing the run's rank should run after this.
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SubmitHighScore;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HighScoreEntry {
+    pub score: u64,
+    pub skulls_killed: usize,
+    pub longest_chain: u64,
+    pub time_survived_secs: f32,
+    pub seed: RunSeed,
+}
+
+#[derive(Debug, Default, Resource, Serialize, Deserialize)]
+pub struct HighScores {
+    /// Sorted from highest to lowest score.
+    pub entries: Vec<HighScoreEntry>,
+
+    /// Index into `entries` of the most recently finished run, if it made the table.
+    #[serde(skip)]
+    pub last_run: Option<usize>,
+}
+
+impl HighScores {
+    const KEY: &'static str = "highscores.ron";
+    /// How many runs are kept. More than are shown, so ranks further down still mean something.
+    const MAX_ENTRIES: usize = 100;
+
+    fn load() -> Self {
+        let bytes = match storage::load(Self::KEY) {
+            Ok(Some(bytes)) => bytes,
+            Ok(None) => return HighScores::default(),
+            Err(e) => {
+                warn!("failed to load high scores: {e}");
+                return HighScores::default();
+            }
+        };
+        match ron::de::from_bytes(&bytes) {
+            Ok(scores) => scores,
+            Err(e) => {
+                warn!("failed to parse high scores: {e}");
+                HighScores::default()
+            }
+        }
+    }
+
+    fn save(&self) {
+        let text = match ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()) {
+            Ok(text) => text,
+            Err(e) => {
+                error!("failed to serialize high scores: {e}");
+                return;
+            }
+        };
+        if let Err(e) = storage::save(Self::KEY, text.as_bytes()) {
+            error!("failed to save high scores: {e}");
+        }
+    }
+
+    /// Inserts `entry` in order and returns its index, or `None` if it didn't make the table.
+    fn insert(&mut self, entry: HighScoreEntry) -> Option<usize> {
+        // ties go to the earlier run
+        let index = self
+            .entries
+            .iter()
+            .position(|e| e.score < entry.score)
+            .unwrap_or(self.entries.len());
+        if index >= Self::MAX_ENTRIES {
+            return None;
+        }
+        self.entries.insert(index, entry);
+        self.entries.truncate(Self::MAX_ENTRIES);
+        Some(index)
+    }
+}
+
+fn submit_run(
+    mut high_scores: ResMut<HighScores>,
+    score: Res<Score>,
+    stats: Res<RunStats>,
+    kill_count: Res<SkullsKilled>,
+    seed: Res<RunSeed>,
+) {
+    let entry = HighScoreEntry {
+        score: score.score,
+        skulls_killed: kill_count.count,
+        longest_chain: stats.longest_chain,
+        time_survived_secs: stats.time_survived.as_secs_f32(),
+        seed: *seed,
+    };
+    high_scores.last_run = high_scores.insert(entry);
+    high_scores.save();
+}
@@ -5,7 +5,15 @@ use bevy_asset_loader::asset_collection::AssetCollection;
 use num_format::{Locale, WriteFormatted};
 
 use crate::{
-    assets::AssetLoadingExt, health::Health, player::Player, score::Score, states::GameState,
+    assets::AssetLoadingExt,
+    character_controller::Dash,
+    game_mode::{GameMode, in_game_mode},
+    health::Health,
+    player::Player,
+    score::{Combo, Score},
+    states::GameState,
+    wave::{WavePhase, WaveState},
+    weapon::AltWeapon,
 };
 
 #[derive(Debug, Default)]
@@ -15,7 +23,17 @@ impl Plugin for HudPlugin {
     fn build(&self, app: &mut App) {
         app.load_asset_on_startup::<HudAssets>()
             .add_systems(OnEnter(GameState::InGame), setup_hud)
-            .add_systems(Update, (update_health_display, update_score_display));
+            .add_systems(
+                Update,
+                (
+                    update_health_display,
+                    update_score_display,
+                    update_combo_display,
+                    update_alt_ammo_display,
+                    update_dash_display,
+                    update_wave_display.run_if(in_game_mode(GameMode::Waves)),
+                ),
+            );
     }
 }
 
@@ -38,6 +56,14 @@ struct UiCamera;
 struct HealthDisplay;
 #[derive(Debug, Default, Component)]
 struct ScoreDisplay;
+#[derive(Debug, Default, Component)]
+struct ComboDisplay;
+#[derive(Debug, Default, Component)]
+struct WaveDisplay;
+#[derive(Debug, Default, Component)]
+struct AltAmmoDisplay;
+#[derive(Debug, Default, Component)]
+struct DashDisplay;
 
 fn setup_hud(mut commands: Commands, assets: Res<HudAssets>) {
     commands.spawn((UiCamera, StateScoped(GameState::InGame)));
@@ -78,6 +104,78 @@ fn setup_hud(mut commands: Commands, assets: Res<HudAssets>) {
         },
         StateScoped(GameState::InGame),
     ));
+
+    commands.spawn((
+        ComboDisplay,
+        Text::new(""),
+        TextFont {
+            font: assets.font.clone(),
+            font_size: FONT_SIZE * 0.75,
+            ..Default::default()
+        },
+        TextShadow::default(),
+        Node {
+            position_type: PositionType::Absolute,
+            left: Val::Percent(5.0),
+            top: Val::Percent(11.0),
+            ..Default::default()
+        },
+        StateScoped(GameState::InGame),
+    ));
+
+    commands.spawn((
+        WaveDisplay,
+        Text::new(""),
+        TextFont {
+            font: assets.font.clone(),
+            font_size: FONT_SIZE,
+            ..Default::default()
+        },
+        TextShadow::default(),
+        Node {
+            position_type: PositionType::Absolute,
+            right: Val::Percent(5.0),
+            top: Val::Percent(5.0),
+            ..Default::default()
+        },
+        StateScoped(GameState::InGame),
+    ));
+
+    commands.spawn((
+        AltAmmoDisplay,
+        Text::new(""),
+        TextFont {
+            font: assets.font.clone(),
+            font_size: FONT_SIZE * 0.75,
+            ..Default::default()
+        },
+        TextShadow::default(),
+        Node {
+            position_type: PositionType::Absolute,
+            right: Val::Percent(5.0),
+            bottom: Val::Percent(5.0),
+            ..Default::default()
+        },
+        StateScoped(GameState::InGame),
+    ));
+
+    commands.spawn((
+        DashDisplay,
+        Text::new(""),
+        TextFont {
+            font: assets.font.clone(),
+            font_size: FONT_SIZE * 0.75,
+            ..Default::default()
+        },
+        TextShadow::default(),
+        Node {
+            position_type: PositionType::Absolute,
+            right: Val::Percent(5.0),
+            bottom: Val::Percent(11.0),
+            ..Default::default()
+        },
+        StateScoped(GameState::InGame),
+    ));
 }
 
 fn update_health_display(
@@ -112,3 +210,77 @@ fn update_score_display(mut hud_query: Query<&mut Text, With<ScoreDisplay>>, sco
         let _ = buf.write_formatted(&score, &Locale::en);
     }
 }
+
+fn update_combo_display(mut hud_query: Query<&mut Text, With<ComboDisplay>>, combo: Res<Combo>) {
+    if !combo.is_changed() {
+        return;
+    }
+
+    for mut text in hud_query.iter_mut() {
+        let buf = &mut text.0;
+        buf.clear();
+        // no point showing a multiplier that doesn't multiply anything
+        if combo.multiplier > 1.0 {
+            let _ = write!(buf, "x{:.1}", combo.multiplier);
+        }
+    }
+}
+
+fn update_alt_ammo_display(
+    mut hud_query: Query<&mut Text, With<AltAmmoDisplay>>,
+    player_query: Option<Single<&AltWeapon, (With<Player>, Changed<AltWeapon>)>>,
+) {
+    let Some(alt) = player_query else {
+        return;
+    };
+    let weapon = &alt.0;
+
+    for mut text in hud_query.iter_mut() {
+        let buf = &mut text.0;
+        buf.clear();
+        let _ = write!(
+            buf,
+            "{} {}/{}",
+            weapon.def.name, weapon.rounds, weapon.def.magazine
+        );
+    }
+}
+
+fn update_dash_display(
+    mut hud_query: Query<&mut Text, With<DashDisplay>>,
+    player_query: Option<Single<&Dash, (With<Player>, Changed<Dash>)>>,
+) {
+    let Some(dash) = player_query else {
+        return;
+    };
+
+    for mut text in hud_query.iter_mut() {
+        let buf = &mut text.0;
+        buf.clear();
+        if dash.ready() {
+            let _ = buf.write_str("Dash ready");
+        } else {
+            let _ = write!(buf, "Dash {:.1}", dash.cooldown.remaining_secs());
+        }
+    }
+}
+
+fn update_wave_display(mut hud_query: Query<&mut Text, With<WaveDisplay>>, waves: Res<WaveState>) {
+    for mut text in hud_query.iter_mut() {
+        let buf = &mut text.0;
+        buf.clear();
+        match waves.phase {
+            WavePhase::Intermission(ref timer) => {
+                let _ = write!(
+                    buf,
+                    "Wave {} in {}",
+                    waves.number + 1,
+                    timer.remaining_secs().ceil()
+                );
+            }
+            WavePhase::Active { .. } => {
+                let _ = write!(buf, "Wave {}", waves.number);
+            }
+        }
+    }
+}
@@ -5,7 +5,7 @@ use bevy::{
 use bevy_fix_cursor_unlock_web::prelude::*;
 use leafwing_input_manager::prelude::*;
 
-use crate::states::PauseState;
+use crate::{replay::ReplayPlayback, states::PauseState};
 
 #[derive(Debug, Default)]
 pub struct InputPlugin;
@@ -20,8 +20,13 @@ impl Plugin for InputPlugin {
             .init_resource::<InputSettings>()
             .init_resource::<InputState>()
             .insert_resource(default_input_map())
-            .add_systems(Update, handle_input_state)
-            .add_systems(OnEnter(PauseState::Paused), release_mouse);
+            // the cursor is left alone while watching a replay
+            .add_systems(
+                Update,
+                handle_input_state.run_if(not(resource_exists::<ReplayPlayback>)),
+            )
+            .add_systems(OnEnter(PauseState::Paused), release_mouse)
+            .add_systems(OnEnter(PauseState::Shopping), release_mouse);
     }
 }
 
@@ -57,6 +62,15 @@ pub enum InputAction {
     TurnAxis,
     FireMouse,
     FireSpace,
+    AltFireMouse,
+    AltFireKey,
+    NextWeapon,
+    PreviousWeapon,
+    SelectWeapon1,
+    SelectWeapon2,
+    SelectWeapon3,
+    LeaveShop,
+    Dash,
     Focus,
     Pause,
 }
@@ -69,7 +83,10 @@ fn default_input_map() -> InputMap<InputAction> {
         (InputAction::StrafeLeft, KeyCode::KeyA),
         (InputAction::StrafeRight, KeyCode::KeyD),
     ]);
-    map.insert_multiple([(InputAction::FireMouse, MouseButton::Left)]);
+    map.insert_multiple([
+        (InputAction::FireMouse, MouseButton::Left),
+        (InputAction::AltFireMouse, MouseButton::Right),
+    ]);
     map.insert_axis(InputAction::TurnAxis, MouseMoveAxis::X);
 
     map.insert_multiple([
@@ -78,7 +95,25 @@ fn default_input_map() -> InputMap<InputAction> {
         (InputAction::TurnLeft, KeyCode::ArrowLeft),
         (InputAction::TurnRight, KeyCode::ArrowRight),
     ]);
-    map.insert_multiple([(InputAction::FireSpace, KeyCode::Space)]);
+    map.insert_multiple([
+        (InputAction::FireSpace, KeyCode::Space),
+        (InputAction::AltFireKey, KeyCode::KeyE),
+    ]);
+
+    map.insert_multiple([
+        (InputAction::NextWeapon, KeyCode::KeyQ),
+        (InputAction::SelectWeapon1, KeyCode::Digit1),
+        (InputAction::SelectWeapon2, KeyCode::Digit2),
+        (InputAction::SelectWeapon3, KeyCode::Digit3),
+    ]);
+    map.insert_multiple([
+        (InputAction::NextWeapon, MouseScrollDirection::DOWN),
+        (InputAction::PreviousWeapon, MouseScrollDirection::UP),
+    ]);
+
+    map.insert(InputAction::LeaveShop, KeyCode::Enter);
+
+    map.insert(InputAction::Dash, KeyCode::ShiftLeft);
 
     map.insert(InputAction::Pause, KeyCode::Escape);
     map.insert(InputAction::Focus, MouseButton::Left);
@@ -5,7 +5,7 @@ use bevy_sprite3d::prelude::*;
 
 use crate::{
     assets::AssetLoadingExt,
-    physics::{PLAYER_GROUP, WALL_GROUP},
+    physics::{EXPLOSION_GROUP, PLAYER_GROUP, WALL_GROUP, WEAPON_GROUP},
     states::GameState,
 };
 
@@ -13,22 +13,34 @@ use crate::{
 pub struct LevelPlugin;
 
 impl Plugin for LevelPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::InGame), spawn_walls);
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct LevelVisualsPlugin;
+
+impl Plugin for LevelVisualsPlugin {
     fn build(&self, app: &mut App) {
         app.load_asset_on_startup::<LevelAssets>()
-            .add_systems(OnEnter(GameState::InGame), spawn_ground_and_walls);
+            .add_systems(OnEnter(GameState::InGame), spawn_ground);
     }
 }
 
-fn spawn_ground_and_walls(
+// width of the platform is 2*HALF_WIDTH + 1 to guarantee it has odd side lengths
+const HALF_WIDTH: usize = 7;
+const WIDTH: usize = 2 * HALF_WIDTH + 1;
+const TILE_SIZE: f32 = 4.0;
+/// Distance from the centre of the arena to the walls.
+pub const ARENA_HALF_SIZE: f32 = 0.5 * WIDTH as f32 * TILE_SIZE;
+
+fn spawn_ground(
     mut commands: Commands,
     assets: Res<LevelAssets>,
     mut sprite3d_params: Sprite3dParams,
 ) {
-    // width of the platform is 2*HALF_WIDTH + 1 to guarantee it has odd side lengths
-    const HALF_WIDTH: usize = 7;
-    const WIDTH: usize = 2 * HALF_WIDTH + 1;
     const NUM_TILES: usize = WIDTH * WIDTH;
-    const TILE_SIZE: f32 = 4.0;
 
     // ground
     for i in 0..NUM_TILES {
@@ -81,12 +93,13 @@ fn spawn_ground_and_walls(
             StateScoped(GameState::InGame),
         ));
     }
+}
 
-    // walls
-    const WALL_HALF_SIZE: f32 = 0.5 * WIDTH as f32 * TILE_SIZE;
+fn spawn_walls(mut commands: Commands) {
+    const WALL_HALF_SIZE: f32 = ARENA_HALF_SIZE;
     let groups = CollisionGroups {
         memberships: WALL_GROUP,
-        filters: PLAYER_GROUP,
+        filters: PLAYER_GROUP | EXPLOSION_GROUP | WEAPON_GROUP,
     };
     let collider = Collider::cuboid(WALL_HALF_SIZE, 4.0, WALL_HALF_SIZE);
     for x in [-1, 0, 1] {
@@ -0,0 +1,93 @@
+use bevy::prelude::*;
+
+pub mod assets;
+pub mod audio;
+pub mod camera;
+pub mod chain;
+pub mod character_controller;
+pub mod enemy;
+pub mod explosion;
+pub mod fire_skull;
+pub mod game_mode;
+pub mod headless;
+pub mod health;
+pub mod highscore;
+pub mod hud;
+pub mod input;
+pub mod level;
+pub mod menu;
+pub mod pause_menu;
+pub mod physics;
+pub mod pickup;
+pub mod player;
+pub mod projectile;
+pub mod rand;
+pub mod replay;
+pub mod score;
+pub mod score_popup;
+pub mod shop;
+pub mod spatial;
+pub mod spawner;
+pub mod sprite;
+pub mod states;
+pub mod steering;
+pub mod storage;
+pub mod wave;
+pub mod weapon;
+
+/// Everything that drives the simulation itself. Doesn't need a window, renderer or audio
+/// backend, so it can also be run by the [`headless`] harness.
+#[derive(Debug, Default)]
+pub struct GameplayPlugin;
+
+impl Plugin for GameplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+            player::PlayerPlugin,
+            character_controller::CharacterControllerPlugin,
+            physics::PhysicsPlugin,
+            fire_skull::FireSkullPlugin,
+            weapon::WeaponPlugin,
+            health::HealthPlugin,
+            explosion::ExplosionPlugin,
+            level::LevelPlugin,
+            spawner::SpawnerPlugin,
+            rand::RandPlugin,
+            score::ScorePlugin,
+            replay::ReplayPlugin,
+            chain::ChainPlugin,
+        ))
+        .add_plugins((
+            wave::WavePlugin,
+            projectile::ProjectilePlugin,
+            steering::SteeringPlugin,
+            spatial::SpatialPlugin,
+            pickup::PickupPlugin,
+            shop::ShopPlugin,
+        ));
+    }
+}
+
+/// Visuals and audio for the things [`GameplayPlugin`] simulates.
+#[derive(Debug, Default)]
+pub struct PresentationPlugin;
+
+impl Plugin for PresentationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+            audio::AudioPlugin,
+            camera::CameraPlugin,
+            sprite::SpritePlugin,
+            explosion::ExplosionVisualsPlugin,
+            fire_skull::FireSkullVisualsPlugin,
+            level::LevelVisualsPlugin,
+            player::PlayerVisualsPlugin,
+            weapon::WeaponVisualsPlugin,
+            spawner::SpawnerVisualsPlugin,
+            projectile::ProjectileVisualsPlugin,
+            hud::HudPlugin,
+            score_popup::ScorePopupPlugin,
+            pickup::PickupVisualsPlugin,
+        ));
+    }
+}
@@ -1,26 +1,10 @@
 use bevy::asset::AssetMetaCheck;
 use bevy::prelude::*;
 
-mod assets;
-mod audio;
-mod camera;
-mod character_controller;
-mod explosion;
-mod fire_skull;
-mod health;
-mod hud;
-mod input;
-mod level;
-mod menu;
-mod pause_menu;
-mod physics;
-mod player;
-mod rand;
-mod score;
-mod shotgun;
-mod spawner;
-mod sprite;
-mod states;
+use exploding_skulls::{
+    GameplayPlugin, PresentationPlugin, assets, highscore, input, menu, pause_menu, shop, spawner,
+    states, wave, weapon,
+};
 
 fn main() {
     App::new()
@@ -58,27 +42,16 @@ fn main() {
         .add_plugins(states::StatesPlugin)
         .add_plugins(assets::AssetPlugin)
         .add_plugins((
-            audio::AudioPlugin,
-            player::PlayerPlugin,
-            character_controller::CharacterControllerPlugin,
             input::InputPlugin,
-            physics::PhysicsPlugin,
-            camera::CameraPlugin,
-            fire_skull::FireSkullPlugin,
-            sprite::SpritePlugin,
-            shotgun::ShotgunPlugin,
-            health::HealthPlugin,
-            explosion::ExplosionPlugin,
-            level::LevelPlugin,
-            spawner::SpawnerPlugin,
-            rand::RandPlugin,
-            score::ScorePlugin,
-        ))
-        // too many plugins, starting another tuple
-        .add_plugins((
-            hud::HudPlugin,
+            GameplayPlugin,
+            PresentationPlugin,
             pause_menu::PauseMenuPlugin,
+            shop::ShopMenuPlugin,
             menu::MenuPlugin,
+            spawner::SpawnDirectorAssetPlugin,
+            wave::WaveTableAssetPlugin,
+            weapon::WeaponLoadoutAssetPlugin,
+            highscore::HighScorePlugin,
         ))
         .run();
 }
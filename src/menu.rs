@@ -1,10 +1,23 @@
+use std::fmt::Write;
+
 use bevy::{prelude::*, window::PrimaryWindow};
 use bevy_asset_loader::prelude::*;
+use bevy_egui::{
+    EguiContextPass, EguiContexts,
+    egui::{self, Align2, RichText},
+};
 use leafwing_input_manager::prelude::ActionState;
+use num_format::{Locale, WriteFormatted};
 
 use crate::{
     assets::AssetLoadingExt,
+    game_mode::GameMode,
+    highscore::{HighScores, SubmitHighScore},
     input::InputAction,
+    rand::{NextRunSeed, RunSeed},
+    replay::{Recording, Replay, ReplayPlayback},
+    score::{RunStats, Score},
+    spawner::SkullsKilled,
     states::{AppState, GameState},
 };
 
@@ -14,20 +27,37 @@ pub struct MenuPlugin;
 impl Plugin for MenuPlugin {
     fn build(&self, app: &mut App) {
         app.load_asset_on_startup::<MenuAssets>()
+            .init_resource::<SeedEntry>()
+            .init_resource::<SavedReplays>()
+            .init_resource::<SavedRecording>()
             // load this first so we can use it on the loading screen:
             .configure_loading_state(
                 LoadingStateConfig::new(AppState::PreLoading).load_collection::<LoadingAssets>(),
             )
             .add_systems(OnEnter(AppState::AssetLoading), setup_loading_screen)
-            .add_systems(OnEnter(GameState::MainMenu), setup_main_menu)
+            .add_systems(
+                OnEnter(GameState::MainMenu),
+                (setup_main_menu, load_saved_replays),
+            )
             .add_systems(
                 Update,
                 (main_menu, scale_splash).run_if(in_state(GameState::MainMenu)),
             )
-            .add_systems(OnEnter(GameState::End), setup_end_screen)
+            .add_systems(
+                EguiContextPass,
+                (
+                    seed_entry.run_if(in_state(GameState::MainMenu)),
+                    replay_list.run_if(in_state(GameState::MainMenu)),
+                    end_screen_actions.run_if(in_state(GameState::End)),
+                ),
+            )
+            .add_systems(
+                OnEnter(GameState::End),
+                setup_end_screen.after(SubmitHighScore),
+            )
             .add_systems(
                 Update,
-                (end_screen, scale_splash).run_if(in_state(GameState::End)),
+                (end_screen, fade_in, scale_splash).run_if(in_state(GameState::End)),
             );
     }
 }
@@ -38,6 +68,8 @@ struct MenuAssets {
     main_menu: Handle<Image>,
     #[asset(path = "textures/end.png")]
     end: Handle<Image>,
+    #[asset(path = "fonts/Bore Blasters 21.ttf")]
+    font: Handle<Font>,
 }
 
 #[derive(Resource, AssetCollection, Debug)]
@@ -80,21 +112,157 @@ fn setup_main_menu(
     ));
 }
 
+/// Seed typed into the main menu. Left empty for a random seed.
+#[derive(Debug, Default, Resource)]
+struct SeedEntry {
+    text: String,
+    has_focus: bool,
+}
+
+impl SeedEntry {
+    fn parse(&self) -> Result<Option<RunSeed>, std::num::ParseIntError> {
+        if self.text.trim().is_empty() {
+            return Ok(None);
+        }
+        self.text.parse().map(Some)
+    }
+}
+
+fn seed_entry(
+    mut contexts: EguiContexts,
+    main_window: Single<&Window, With<PrimaryWindow>>,
+    mut entry: ResMut<SeedEntry>,
+    mut mode: ResMut<GameMode>,
+) {
+    egui::Window::new("Run")
+        .auto_sized()
+        .movable(false)
+        .collapsible(false)
+        .title_bar(false)
+        .pivot(Align2::CENTER_BOTTOM)
+        .default_pos([main_window.width() / 2.0, main_window.height() - 20.0])
+        .show(contexts.ctx_mut(), |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Mode");
+                let mut selected = *mode;
+                for option in GameMode::ALL {
+                    ui.selectable_value(&mut selected, option, option.to_string());
+                }
+                // only write it back when it changes, so change detection means something
+                mode.set_if_neq(selected);
+            });
+            ui.horizontal(|ui| {
+                ui.label("Seed");
+                let response =
+                    ui.add(egui::TextEdit::singleline(&mut entry.text).hint_text("random"));
+                entry.has_focus = response.has_focus();
+            });
+            if entry.parse().is_err() {
+                ui.label(RichText::new("Invalid seed").color(egui::Color32::RED));
+            }
+        });
+}
+
+/// Replays that can be watched from the main menu. Reloaded whenever the menu is entered.
+#[derive(Debug, Default, Resource)]
+struct SavedReplays(Vec<(String, Replay)>);
+
+fn load_saved_replays(mut saved: ResMut<SavedReplays>) {
+    saved.0 = Replay::load_all();
+}
+
+fn replay_list(
+    mut commands: Commands,
+    mut contexts: EguiContexts,
+    saved: Res<SavedReplays>,
+    playback: Option<Res<ReplayPlayback>>,
+) {
+    if saved.0.is_empty() {
+        return;
+    }
+
+    egui::Window::new("Replays")
+        .movable(false)
+        .collapsible(true)
+        .default_open(false)
+        .anchor(Align2::RIGHT_TOP, [-10.0, 10.0])
+        .show(contexts.ctx_mut(), |ui| {
+            egui::ScrollArea::vertical()
+                .max_height(300.0)
+                .show(ui, |ui| {
+                    egui::Grid::new("replay_grid").show(ui, |ui| {
+                        for (key, replay) in saved.0.iter() {
+                            let secs = replay.duration().as_secs();
+                            ui.label(key);
+                            ui.label(RichText::new(replay.seed.to_string()).monospace());
+                            ui.label(format!("{}:{:02}", secs / 60, secs % 60));
+                            if ui
+                                .add_enabled(playback.is_none(), egui::Button::new("Watch"))
+                                .clicked()
+                            {
+                                commands.insert_resource(ReplayPlayback::new(replay.clone()));
+                            }
+                            ui.end_row();
+                        }
+                    });
+                });
+        });
+}
+
 fn main_menu(
     mut input: ResMut<ActionState<InputAction>>,
     mut next_state: ResMut<NextState<GameState>>,
+    mut next_seed: ResMut<NextRunSeed>,
+    entry: Res<SeedEntry>,
 ) {
+    if entry.has_focus {
+        return;
+    }
+
     if input.just_pressed(&InputAction::FireSpace) {
+        let Ok(seed) = entry.parse() else {
+            return;
+        };
+        if seed.is_some() {
+            next_seed.0 = seed;
+        }
+
         input.release(&InputAction::FireSpace);
         next_state.set(GameState::InGame);
     }
 }
 
+/// Black overlay that fades away when the end screen appears, continuing the fade from the
+/// death screen.
+#[derive(Debug, Component)]
+struct FadeIn(Timer);
+
+fn fade_in(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut FadeIn, &mut BackgroundColor)>,
+) {
+    for (entity, mut fade, mut color) in query.iter_mut() {
+        fade.0.tick(time.delta());
+        color.0.set_alpha(1.0 - fade.0.fraction());
+        if fade.0.finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
 fn setup_end_screen(
     mut commands: Commands,
     assets: Res<MenuAssets>,
     window: Single<&Window, With<PrimaryWindow>>,
+    seed: Res<RunSeed>,
+    score: Res<Score>,
+    stats: Res<RunStats>,
+    kill_count: Res<SkullsKilled>,
+    high_scores: Res<HighScores>,
+    mut saved: ResMut<SavedRecording>,
 ) {
+    saved.0 = None;
     commands.spawn((Camera2d, StateScoped(GameState::End)));
     commands.spawn((
         Sprite {
@@ -104,8 +272,176 @@ fn setup_end_screen(
         StateScoped(GameState::End),
         Splash,
     ));
+
+    commands.spawn((
+        Text::new(format!("Seed: {}", *seed)),
+        TextFont {
+            font: assets.font.clone(),
+            font_size: 30.0,
+            ..Default::default()
+        },
+        TextShadow::default(),
+        Node {
+            position_type: PositionType::Absolute,
+            right: Val::Percent(5.0),
+            bottom: Val::Percent(5.0),
+            ..Default::default()
+        },
+        StateScoped(GameState::End),
+    ));
+
+    let text_font = |font_size: f32| TextFont {
+        font: assets.font.clone(),
+        font_size,
+        ..Default::default()
+    };
+    let formatted = |n: u64| {
+        let mut buf = String::new();
+        let _ = buf.write_formatted(&n, &Locale::en);
+        buf
+    };
+
+    let accuracy = match stats.accuracy() {
+        Some(accuracy) => format!("{:.0}%", accuracy * 100.0),
+        None => "-".to_string(),
+    };
+    let secs = stats.time_survived.as_secs();
+    let summary = [
+        format!("Kills: {}", kill_count.count),
+        format!("Longest chain: {}", stats.longest_chain),
+        format!("Accuracy: {accuracy}"),
+        format!("Time survived: {}:{:02}", secs / 60, secs % 60),
+    ];
+
+    let mut rank = String::new();
+    match high_scores.last_run {
+        Some(index) => {
+            let _ = write!(rank, "Rank #{}", index + 1);
+        }
+        None => rank.push_str("Unranked"),
+    }
+
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                right: Val::Percent(5.0),
+                top: Val::Percent(10.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::End,
+                row_gap: Val::Px(4.0),
+                ..Default::default()
+            },
+            StateScoped(GameState::End),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(format!("Score: {}", formatted(score.score))),
+                text_font(50.0),
+                TextShadow::default(),
+            ));
+            for line in summary {
+                parent.spawn((Text::new(line), text_font(30.0), TextShadow::default()));
+            }
+            parent.spawn((
+                Text::new(rank),
+                text_font(35.0),
+                TextShadow::default(),
+                Node {
+                    margin: UiRect::top(Val::Px(20.0)),
+                    ..Default::default()
+                },
+            ));
+            parent.spawn((
+                Text::new("Top Scores"),
+                text_font(30.0),
+                TextShadow::default(),
+                Node {
+                    margin: UiRect::top(Val::Px(20.0)),
+                    ..Default::default()
+                },
+            ));
+            for (i, entry) in high_scores.entries.iter().take(10).enumerate() {
+                let color = if high_scores.last_run == Some(i) {
+                    Color::srgb(1.0, 0.85, 0.2)
+                } else {
+                    Color::WHITE
+                };
+                parent.spawn((
+                    Text::new(format!(
+                        "{}. {}  ({} skulls, chain {})",
+                        i + 1,
+                        formatted(entry.score),
+                        entry.skulls_killed,
+                        entry.longest_chain,
+                    )),
+                    text_font(25.0),
+                    TextColor(color),
+                    TextShadow::default(),
+                ));
+            }
+        });
+
+    // spawned last so it's drawn over everything else
+    commands.spawn((
+        FadeIn(Timer::from_seconds(0.5, TimerMode::Once)),
+        Node {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            ..Default::default()
+        },
+        BackgroundColor(Color::BLACK),
+        GlobalZIndex(i32::MAX),
+        StateScoped(GameState::End),
+    ));
+}
+
+/// Where the last run's replay was saved, if it has been.
+#[derive(Debug, Default, Resource)]
+struct SavedRecording(Option<String>);
+
+fn end_screen_actions(
+    mut contexts: EguiContexts,
+    mut next_state: ResMut<NextState<GameState>>,
+    recording: Option<Res<Recording>>,
+    mut saved: ResMut<SavedRecording>,
+) {
+    egui::Window::new("Run Over")
+        .auto_sized()
+        .movable(false)
+        .collapsible(false)
+        .title_bar(false)
+        .anchor(Align2::LEFT_BOTTOM, [10.0, -10.0])
+        .show(contexts.ctx_mut(), |ui| {
+            ui.horizontal(|ui| {
+                if ui.button("Retry").clicked() {
+                    next_state.set(GameState::InGame);
+                }
+                if ui.button("Main Menu").clicked() {
+                    next_state.set(GameState::MainMenu);
+                }
+
+                // nothing is recorded while watching a replay
+                let Some(recording) = recording else {
+                    return;
+                };
+                if let Some(ref key) = saved.0 {
+                    ui.label(format!("Saved as {key}"));
+                } else if ui.button("Save Replay").clicked() {
+                    match recording.0.save() {
+                        Ok(key) => {
+                            info!("saved replay as {key}");
+                            saved.0 = Some(key);
+                        }
+                        Err(e) => error!("failed to save replay: {e}"),
+                    }
+                }
+            });
+        });
 }
 
+/// Keyboard shortcuts for the end screen buttons.
 fn end_screen(
     mut input: ResMut<ActionState<InputAction>>,
     mut next_state: ResMut<NextState<GameState>>,
@@ -113,6 +449,9 @@ fn end_screen(
     if input.just_pressed(&InputAction::FireSpace) {
         input.release(&InputAction::FireSpace);
         next_state.set(GameState::InGame);
+    } else if input.just_pressed(&InputAction::Pause) {
+        input.release(&InputAction::Pause);
+        next_state.set(GameState::MainMenu);
     }
 }
 
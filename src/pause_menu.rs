@@ -9,6 +9,7 @@ use crate::{
     audio::AudioSettings,
     camera::CameraSettings,
     input::{InputAction, InputSettings},
+    rand::RunSeed,
     states::{GameState, PauseState},
 };
 
@@ -118,6 +119,8 @@ fn pause_unpause(
     match *current_state.get() {
         PauseState::Paused => next_state.set(PauseState::Unpaused),
         PauseState::Unpaused => next_state.set(PauseState::Paused),
+        // the shop is left with its own button
+        PauseState::Shopping => {}
     }
 }
 
@@ -133,6 +136,7 @@ fn pause_menu(
     mut contexts: EguiContexts,
     main_window: Single<&Window, With<PrimaryWindow>>,
     mut settings: ResMut<Settings>,
+    seed: Res<RunSeed>,
     mut exit_confirm: Local<bool>,
     mut next_pause_state: ResMut<NextState<PauseState>>,
     mut next_game_state: ResMut<NextState<GameState>>,
@@ -173,6 +177,15 @@ fn pause_menu(
                 ui.label("FOV");
                 ui.add(settings.fov.slider());
                 ui.end_row();
+
+                ui.vertical_centered(|ui| ui.heading("Run"));
+                ui.end_row();
+
+                ui.label("Seed");
+                ui.add(egui::Label::new(
+                    RichText::new(seed.to_string()).monospace(),
+                ));
+                ui.end_row();
             });
             ui.add_space(5.0);
             ui.horizontal(|ui| {
@@ -9,13 +9,20 @@ impl Plugin for PhysicsPlugin {
         rapier_configuration.gravity = Vec3::ZERO;
 
         app.add_plugins(
-            RapierPhysicsPlugin::<NoUserData>::default().with_custom_initialization(
-                RapierContextInitialization::InitializeDefaultRapierContext {
-                    rapier_configuration,
-                    integration_parameters: IntegrationParameters::default(),
-                },
-            ),
+            RapierPhysicsPlugin::<NoUserData>::default()
+                .in_fixed_schedule()
+                .with_custom_initialization(
+                    RapierContextInitialization::InitializeDefaultRapierContext {
+                        rapier_configuration,
+                        integration_parameters: IntegrationParameters::default(),
+                    },
+                ),
         )
+        // step once per tick of the gameplay systems, which all run in `FixedUpdate`
+        .insert_resource(TimestepMode::Fixed {
+            dt: Time::<Fixed>::default().timestep().as_secs_f32(),
+            substeps: 1,
+        })
         // .add_plugins(RapierDebugRenderPlugin::default())
         ;
     }
@@ -23,6 +30,8 @@ impl Plugin for PhysicsPlugin {
 
 pub const PLAYER_GROUP: Group = Group::GROUP_1;
 pub const ENEMY_GROUP: Group = Group::GROUP_2;
-pub const SHOTGUN_GROUP: Group = Group::GROUP_3;
+pub const WEAPON_GROUP: Group = Group::GROUP_3;
 pub const EXPLOSION_GROUP: Group = Group::GROUP_4;
 pub const WALL_GROUP: Group = Group::GROUP_5;
+pub const PROJECTILE_GROUP: Group = Group::GROUP_6;
+pub const PICKUP_GROUP: Group = Group::GROUP_7;
@@ -0,0 +1,463 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_asset_loader::asset_collection::AssetCollection;
+use bevy_rapier3d::prelude::*;
+use bevy_sprite3d::prelude::*;
+use rand_core::RngCore;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    assets::AssetLoadingExt,
+    health::Health,
+    physics::{PICKUP_GROUP, PLAYER_GROUP},
+    player::Player,
+    rand::{RngStream, RngStreamAppExt, StreamRng},
+    score::{PointsAwarded, Score},
+    sprite::{AnimatedSprite3d, FaceCamera},
+    states::{GameState, PauseState},
+};
+
+#[derive(Debug, Default)]
+pub struct PickupPlugin;
+
+impl Plugin for PickupPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_rng_stream::<DropRng>()
+            .init_resource::<PickupSettings>()
+            .add_state_scoped_event::<DropEvent>(GameState::InGame)
+            .add_state_scoped_event::<PickupCollected>(GameState::InGame)
+            .add_systems(
+                FixedUpdate,
+                (
+                    spawn_drops,
+                    collect_pickups,
+                    expire_pickups,
+                    expire_power_up::<DoubleDamage>,
+                    expire_power_up::<BigExplosions>,
+                    expire_power_up::<RapidReload>,
+                    expire_power_up::<Invulnerable>,
+                    keep_invulnerable,
+                )
+                    .run_if(in_state(GameState::InGame).and(in_state(PauseState::Unpaused))),
+            );
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct PickupVisualsPlugin;
+
+impl Plugin for PickupVisualsPlugin {
+    fn build(&self, app: &mut App) {
+        app.load_asset_on_startup::<PickupAssets>().add_systems(
+            Update,
+            (spawn_pickup_visuals, bob_pickups)
+                .run_if(in_state(GameState::InGame).and(in_state(PauseState::Unpaused))),
+        );
+    }
+}
+
+struct DropRng;
+
+impl RngStream for DropRng {
+    const NAME: &'static str = "drop";
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PickupKind {
+    /// Restores some health.
+    Health,
+    /// Worth points, without touching the combo.
+    ScoreGem,
+    DoubleDamage,
+    BigExplosions,
+    RapidReload,
+    Invulnerability,
+}
+
+/// What an enemy might leave behind when it dies. The default never drops anything.
+#[derive(Debug, Default, Clone, PartialEq, Component, Serialize, Deserialize)]
+pub struct DropTable {
+    /// Chance of dropping anything at all.
+    pub chance: f32,
+    /// Relative weight of each pickup, given that something drops.
+    pub weights: Vec<(PickupKind, f32)>,
+}
+
+impl DropTable {
+    /// The usual mix of pickups, dropped `chance` of the time.
+    pub fn new(chance: f32) -> Self {
+        DropTable {
+            chance,
+            weights: vec![
+                (PickupKind::ScoreGem, 6.0),
+                (PickupKind::Health, 3.0),
+                (PickupKind::DoubleDamage, 1.0),
+                (PickupKind::BigExplosions, 1.0),
+                (PickupKind::RapidReload, 1.0),
+                (PickupKind::Invulnerability, 0.5),
+            ],
+        }
+    }
+
+    /// Rolls for a drop.
+    pub fn roll(&self, rng: &mut impl RngCore) -> Option<PickupKind> {
+        // don't draw from the rng for things that never drop
+        if self.chance <= 0.0 || self.weights.is_empty() {
+            return None;
+        }
+        if unit(rng) >= self.chance {
+            return None;
+        }
+        let total: f32 = self.weights.iter().map(|(_, weight)| weight).sum();
+        let mut roll = unit(rng) * total;
+        for (kind, weight) in self.weights.iter() {
+            if roll < *weight {
+                return Some(*kind);
+            }
+            roll -= weight;
+        }
+        self.weights.last().map(|(kind, _)| *kind)
+    }
+}
+
+fn unit(rng: &mut impl RngCore) -> f32 {
+    rng.next_u32() as f32 / u32::MAX as f32
+}
+
+/// Sent when something with a [`DropTable`] dies.
+#[derive(Debug, Clone, Event)]
+pub struct DropEvent {
+    pub pos: Vec3,
+    pub table: DropTable,
+}
+
+/// Sent when the player picks something up.
+#[derive(Debug, Clone, Copy, Event)]
+pub struct PickupCollected {
+    pub kind: PickupKind,
+    pub pos: Vec3,
+}
+
+/// How strong pickups are and how long they last.
+#[derive(Debug, Clone, Resource)]
+pub struct PickupSettings {
+    /// Seconds a pickup waits to be collected before vanishing.
+    pub lifetime: f32,
+    pub heal: f32,
+    pub gem_points: u64,
+    /// Seconds each power-up lasts.
+    pub power_up_duration: f32,
+    pub invulnerability_duration: f32,
+}
+
+impl Default for PickupSettings {
+    fn default() -> Self {
+        PickupSettings {
+            lifetime: 15.0,
+            heal: 25.0,
+            gem_points: 500,
+            power_up_duration: 10.0,
+            invulnerability_duration: 5.0,
+        }
+    }
+}
+
+#[derive(Debug, Component)]
+#[require(
+    Visibility,
+    RigidBody::KinematicPositionBased,
+    Collider::ball(0.6),
+    Sensor,
+    ActiveEvents::COLLISION_EVENTS,
+    CollisionGroups::new(PICKUP_GROUP, PLAYER_GROUP)
+)]
+pub struct Pickup {
+    pub kind: PickupKind,
+    pub lifetime: Timer,
+}
+
+/// A power-up that wears off once its timer runs out.
+pub trait PowerUp: Component {
+    fn new(duration: Duration) -> Self;
+    fn timer_mut(&mut self) -> &mut Timer;
+}
+
+macro_rules! power_up {
+    ($(#[$attr:meta])* $name:ident) => {
+        $(#[$attr])*
+        #[derive(Debug, Component)]
+        pub struct $name(pub Timer);
+
+        impl PowerUp for $name {
+            fn new(duration: Duration) -> Self {
+                $name(Timer::new(duration, TimerMode::Once))
+            }
+
+            fn timer_mut(&mut self) -> &mut Timer {
+                &mut self.0
+            }
+        }
+    };
+}
+
+power_up!(
+    /// Everything the player shoots does more damage.
+    DoubleDamage
+);
+power_up!(
+    /// Skulls killed while the player has this blow up bigger.
+    BigExplosions
+);
+power_up!(
+    /// The player's weapons reload and recharge faster.
+    RapidReload
+);
+power_up!(
+    /// Keeps [`Player::invulnerability_timer`] from running out.
+    Invulnerable
+);
+
+impl DoubleDamage {
+    pub const MULTIPLIER: f32 = 2.0;
+}
+
+impl BigExplosions {
+    pub const SCALE: f32 = 1.5;
+}
+
+impl RapidReload {
+    pub const SPEED: f32 = 2.0;
+}
+
+fn spawn_drops(
+    mut commands: Commands,
+    mut reader: EventReader<DropEvent>,
+    mut rng: ResMut<StreamRng<DropRng>>,
+    settings: Res<PickupSettings>,
+) {
+    for DropEvent { pos, table } in reader.read() {
+        let Some(kind) = table.roll(&mut *rng) else {
+            continue;
+        };
+        commands.spawn((
+            Pickup {
+                kind,
+                lifetime: Timer::from_seconds(settings.lifetime, TimerMode::Once),
+            },
+            Transform::from_translation(*pos),
+            StateScoped(GameState::InGame),
+        ));
+    }
+}
+
+fn collect_pickups(
+    mut commands: Commands,
+    mut collisions: EventReader<CollisionEvent>,
+    mut writer: EventWriter<PickupCollected>,
+    mut points_writer: EventWriter<PointsAwarded>,
+    mut score: ResMut<Score>,
+    settings: Res<PickupSettings>,
+    pickup_query: Query<(&Pickup, &GlobalTransform)>,
+    mut player_query: Query<(&Player, &mut Health)>,
+    mut collected: Local<Vec<Entity>>,
+) {
+    collected.clear();
+    for ev in collisions.read() {
+        let &CollisionEvent::Started(e1, e2, _flags) = ev else {
+            continue;
+        };
+        let (player, pickup) = if player_query.contains(e1) {
+            (e1, e2)
+        } else if player_query.contains(e2) {
+            (e2, e1)
+        } else {
+            continue;
+        };
+        let Ok((&Pickup { kind, .. }, transform)) = pickup_query.get(pickup) else {
+            continue;
+        };
+        // it can touch the player more than once before it's despawned
+        if collected.contains(&pickup) {
+            continue;
+        }
+        let Ok((player_data, mut health)) = player_query.get_mut(player) else {
+            continue;
+        };
+        if player_data.dead {
+            continue;
+        }
+        collected.push(pickup);
+        commands.entity(pickup).despawn();
+
+        let pos = transform.translation();
+        let duration = Duration::from_secs_f32(settings.power_up_duration);
+        let mut player = commands.entity(player);
+        match kind {
+            PickupKind::Health => {
+                health.current = (health.current + settings.heal).min(health.max);
+            }
+            PickupKind::ScoreGem => {
+                score.add(settings.gem_points);
+                points_writer.write(PointsAwarded {
+                    pos,
+                    points: settings.gem_points,
+                    chain: 0,
+                });
+            }
+            // picking up one that's already active starts it over
+            PickupKind::DoubleDamage => {
+                player.insert(DoubleDamage::new(duration));
+            }
+            PickupKind::BigExplosions => {
+                player.insert(BigExplosions::new(duration));
+            }
+            PickupKind::RapidReload => {
+                player.insert(RapidReload::new(duration));
+            }
+            PickupKind::Invulnerability => {
+                player.insert(Invulnerable::new(Duration::from_secs_f32(
+                    settings.invulnerability_duration,
+                )));
+            }
+        }
+        writer.write(PickupCollected { kind, pos });
+    }
+}
+
+fn expire_pickups(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut Pickup)>,
+) {
+    for (entity, mut pickup) in query.iter_mut() {
+        if pickup.lifetime.tick(time.delta()).just_finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+fn expire_power_up<T: PowerUp>(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut T)>,
+) {
+    for (entity, mut power_up) in query.iter_mut() {
+        if power_up.timer_mut().tick(time.delta()).just_finished() {
+            commands.entity(entity).remove::<T>();
+        }
+    }
+}
+
+fn keep_invulnerable(mut query: Query<&mut Player, With<Invulnerable>>) {
+    for mut player in query.iter_mut() {
+        // the usual short invulnerability after getting hurt covers the moment it wears off
+        player.grant_invulnerability(Player::HURT_INVULNERABILITY);
+    }
+}
+
+#[derive(AssetCollection, Resource, Debug)]
+struct PickupAssets {
+    // use .dds here instead of .png, same as the skulls
+    #[asset(path = "textures/flame_fire.dds")]
+    fire_atlas_texture: Handle<Image>,
+    #[asset(texture_atlas_layout(tile_size_x = 128, tile_size_y = 128, columns = 6, rows = 5))]
+    fire_atlas_layout: Handle<TextureAtlasLayout>,
+
+    #[asset(path = "textures/explosion_fire.png")]
+    explosion_atlas_texture: Handle<Image>,
+    #[asset(texture_atlas_layout(tile_size_x = 128, tile_size_y = 128, columns = 6, rows = 5))]
+    explosion_atlas_layout: Handle<TextureAtlasLayout>,
+
+    #[asset(path = "textures/explosion_magic.png")]
+    magic_atlas_texture: Handle<Image>,
+    #[asset(texture_atlas_layout(tile_size_x = 128, tile_size_y = 128, columns = 6, rows = 5))]
+    magic_atlas_layout: Handle<TextureAtlasLayout>,
+}
+
+fn spawn_pickup_visuals(
+    mut commands: Commands,
+    assets: Res<PickupAssets>,
+    query: Query<(Entity, &Pickup), Added<Pickup>>,
+    mut sprite3d_params: Sprite3dParams,
+) {
+    for (entity, pickup) in query.iter() {
+        // every kind loops its own stretch of animation, so they can be told apart at a glance
+        let (image, layout, frames, pixels_per_metre) = match pickup.kind {
+            PickupKind::Health => (
+                &assets.fire_atlas_texture,
+                &assets.fire_atlas_layout,
+                vec![13, 14, 15, 16, 17, 18, 19, 20, 21],
+                128.0,
+            ),
+            PickupKind::ScoreGem => (
+                &assets.explosion_atlas_texture,
+                &assets.explosion_atlas_layout,
+                vec![6, 7, 8, 9, 10, 11],
+                256.0,
+            ),
+            PickupKind::DoubleDamage => (
+                &assets.magic_atlas_texture,
+                &assets.magic_atlas_layout,
+                vec![6, 7, 8, 9, 10, 11],
+                128.0,
+            ),
+            PickupKind::BigExplosions => (
+                &assets.explosion_atlas_texture,
+                &assets.explosion_atlas_layout,
+                vec![12, 13, 14, 15, 16, 17],
+                128.0,
+            ),
+            PickupKind::RapidReload => (
+                &assets.magic_atlas_texture,
+                &assets.magic_atlas_layout,
+                vec![12, 13, 14, 15, 16, 17],
+                128.0,
+            ),
+            PickupKind::Invulnerability => (
+                &assets.magic_atlas_texture,
+                &assets.magic_atlas_layout,
+                vec![18, 19, 20, 21, 22, 23],
+                96.0,
+            ),
+        };
+        let atlas = TextureAtlas {
+            layout: layout.clone(),
+            index: frames[0],
+        };
+        let animation = AnimatedSprite3d {
+            current: 0,
+            frames,
+            timer: Timer::new(Duration::from_millis(80), TimerMode::Repeating),
+            destroy_when_finished: false,
+        };
+        let sprite = Sprite3dBuilder {
+            image: image.clone(),
+            alpha_mode: AlphaMode::Blend,
+            unlit: true,
+            pixels_per_metre,
+            ..Default::default()
+        }
+        .bundle_with_atlas(&mut sprite3d_params, atlas);
+        let visual = commands
+            .spawn((sprite, animation, FaceCamera::default(), PickupBob))
+            .id();
+
+        if let Ok(mut c) = commands.get_entity(entity) {
+            c.add_child(visual);
+        } else {
+            commands.entity(visual).despawn();
+        }
+    }
+}
+
+/// Bobs a pickup's sprite up and down so it stands out from the fire.
+#[derive(Debug, Default, Component)]
+struct PickupBob;
+
+fn bob_pickups(time: Res<Time>, mut query: Query<&mut Transform, With<PickupBob>>) {
+    let offset = (time.elapsed_secs() * 3.0).sin() * 0.15;
+    for mut transform in query.iter_mut() {
+        transform.translation.y = offset;
+    }
+}
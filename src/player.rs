@@ -6,11 +6,13 @@ use bevy_seedling::prelude::*;
 
 use crate::{
     camera::MainCamera,
-    character_controller::CharacterController,
+    character_controller::{CharacterController, Dash, ReadHeading},
     health::Health,
-    physics::{ENEMY_GROUP, EXPLOSION_GROUP, PLAYER_GROUP, WALL_GROUP},
-    shotgun::{Shotgun, ShotgunViewModel},
+    physics::{
+        ENEMY_GROUP, EXPLOSION_GROUP, PICKUP_GROUP, PLAYER_GROUP, PROJECTILE_GROUP, WALL_GROUP,
+    },
     states::{GameState, PauseState},
+    weapon::{AltWeapon, Weapon, WeaponInventory, WeaponLoadout, WeaponViewModel},
 };
 
 pub struct PlayerPlugin;
@@ -19,9 +21,8 @@ impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
         app.add_state_scoped_event::<PlayerHurtEvent>(GameState::InGame)
             .add_state_scoped_event::<PlayerDeathEvent>(GameState::InGame)
-            .add_systems(Update, on_player_death)
             .add_systems(
-                Update,
+                FixedUpdate,
                 (update_player, handle_player_death)
                     .run_if(in_state(GameState::InGame).and(in_state(PauseState::Unpaused))),
             )
@@ -29,19 +30,40 @@ impl Plugin for PlayerPlugin {
     }
 }
 
+#[derive(Debug, Default)]
+pub struct PlayerVisualsPlugin;
+
+impl Plugin for PlayerVisualsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (on_player_death, fade_out_on_death))
+            .add_systems(
+                PostUpdate,
+                animate_camera_fall
+                    .before(TransformSystem::TransformPropagate)
+                    .run_if(in_state(GameState::InGame).and(in_state(PauseState::Unpaused))),
+            );
+    }
+}
+
 #[derive(Debug, Component)]
 #[require(
     Name::new("Player"),
-    Health::new(100.0),
+    Health = Health::new(100.0),
     Visibility::Visible,
     SpatialListener3D,
     CharacterController = CharacterController {
             max_speed: 15.0,
             acceleration: 10.0,
         },
-    CollisionGroups::new(PLAYER_GROUP, ENEMY_GROUP | EXPLOSION_GROUP | WALL_GROUP),
+    CollisionGroups::new(
+        PLAYER_GROUP,
+        ENEMY_GROUP | EXPLOSION_GROUP | WALL_GROUP | PROJECTILE_GROUP | PICKUP_GROUP
+    ),
     ActiveEvents::COLLISION_EVENTS,
-    Shotgun,
+    Weapon,
+    AltWeapon,
+    WeaponInventory,
+    Dash,
 )]
 pub struct Player {
     pub invulnerability_timer: Timer,
@@ -51,7 +73,7 @@ pub struct Player {
 
 impl Default for Player {
     fn default() -> Self {
-        let mut timer = Timer::from_seconds(0.2, TimerMode::Once);
+        let mut timer = Timer::new(Player::HURT_INVULNERABILITY, TimerMode::Once);
         // start the timer in a "finished" state
         timer.tick(Duration::from_secs_f32(1000.0));
         Player {
@@ -63,9 +85,19 @@ impl Default for Player {
 }
 
 impl Player {
+    /// Invulnerability after getting hurt.
+    pub const HURT_INVULNERABILITY: Duration = Duration::from_millis(200);
+
     pub fn is_vulnerable(&self) -> bool {
         self.invulnerability_timer.finished()
     }
+
+    /// Makes the player invulnerable for at least `duration`.
+    pub fn grant_invulnerability(&mut self, duration: Duration) {
+        if duration > self.invulnerability_timer.remaining() {
+            self.invulnerability_timer = Timer::new(duration, TimerMode::Once);
+        }
+    }
 }
 
 fn update_player(time: Res<Time>, mut query: Query<&mut Player>) {
@@ -74,8 +106,14 @@ fn update_player(time: Res<Time>, mut query: Query<&mut Player>) {
     }
 }
 
-fn spawn_player(mut commands: Commands) {
-    commands.spawn((Player::default(), StateScoped(GameState::InGame)));
+fn spawn_player(mut commands: Commands, loadout: Res<WeaponLoadout>) {
+    commands.spawn((
+        Player::default(),
+        Weapon::new(loadout.primary.clone()),
+        WeaponInventory::new(loadout.holstered.iter().cloned()),
+        AltWeapon(Weapon::new(loadout.alt.clone())),
+        StateScoped(GameState::InGame),
+    ));
 }
 
 #[derive(Debug, Default, Clone, Copy, Event)]
@@ -104,10 +142,28 @@ fn handle_player_death(
     }
 }
 
+/// Drops the camera to the ground and rolls it onto its side after the player dies.
+#[derive(Debug, Component)]
+struct CameraFall {
+    timer: Timer,
+    start_height: f32,
+}
+
+impl CameraFall {
+    const DURATION: f32 = 0.6;
+    const DROP: f32 = 0.25;
+    const ROLL: f32 = 70.0;
+}
+
+/// Full screen overlay that goes black as the death timer runs out.
+#[derive(Debug, Default, Component)]
+struct DeathFade;
+
 fn on_player_death(
+    mut commands: Commands,
     reader: EventReader<PlayerDeathEvent>,
-    mut viewmodel_query: Query<&mut Visibility, With<ShotgunViewModel>>,
-    mut camera: Single<&mut Transform, With<MainCamera>>,
+    mut viewmodel_query: Query<&mut Visibility, With<WeaponViewModel>>,
+    camera: Single<(Entity, &Transform), With<MainCamera>>,
 ) {
     if reader.is_empty() {
         return;
@@ -118,8 +174,49 @@ fn on_player_death(
         *visibility = Visibility::Hidden;
     }
 
-    // drop the view to the ground
-    camera.translation.y -= 0.25;
+    let (camera, transform) = *camera;
+    commands.entity(camera).insert(CameraFall {
+        timer: Timer::from_seconds(CameraFall::DURATION, TimerMode::Once),
+        start_height: transform.translation.y,
+    });
+
+    commands.spawn((
+        DeathFade,
+        Node {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            ..Default::default()
+        },
+        BackgroundColor(Color::BLACK.with_alpha(0.0)),
+        StateScoped(GameState::InGame),
+    ));
+}
+
+fn animate_camera_fall(
+    time: Res<Time>,
+    player: Single<&ReadHeading, With<Player>>,
+    camera: Single<(&mut Transform, &mut CameraFall), With<MainCamera>>,
+) {
+    let (mut transform, mut fall) = camera.into_inner();
+    fall.timer.tick(time.delta());
+    let t = EaseFunction::BounceOut.sample_clamped(fall.timer.fraction());
+
+    transform.translation.y = fall.start_height - CameraFall::DROP * t;
+    // the heading is reapplied every frame, so build the whole rotation here
+    transform.rotation = Quat::from_axis_angle(Vec3::Y, player.heading)
+        * Quat::from_rotation_z(CameraFall::ROLL.to_radians() * t);
+}
+
+fn fade_out_on_death(
+    player: Single<&Player>,
+    mut fade_query: Query<&mut BackgroundColor, With<DeathFade>>,
+) {
+    // start fading once the camera has hit the ground
+    let t = ((player.death_timer.fraction() - 0.4) / 0.6).clamp(0.0, 1.0);
+    for mut color in fade_query.iter_mut() {
+        color.0.set_alpha(t);
+    }
 }
 
 #[derive(Debug, Event)]
@@ -0,0 +1,231 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_asset_loader::asset_collection::AssetCollection;
+use bevy_rapier3d::prelude::*;
+use bevy_sprite3d::prelude::*;
+
+use crate::{
+    assets::AssetLoadingExt,
+    chain::{ChainRoot, ChainTracker},
+    explosion::Falloff,
+    health::{DamageEvent, DamageType, DeathBehavior, Fuse, Health},
+    physics::{EXPLOSION_GROUP, PLAYER_GROUP, PROJECTILE_GROUP, WEAPON_GROUP},
+    player::Player,
+    sprite::{AnimatedSprite3d, FaceCamera},
+    states::{GameState, PauseState},
+};
+
+#[derive(Debug, Default)]
+pub struct ProjectilePlugin;
+
+impl Plugin for ProjectilePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            (fire_projectiles, move_projectiles, projectile_collision)
+                .run_if(in_state(GameState::InGame).and(in_state(PauseState::Unpaused))),
+        );
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ProjectileVisualsPlugin;
+
+impl Plugin for ProjectileVisualsPlugin {
+    fn build(&self, app: &mut App) {
+        app.load_asset_on_startup::<ProjectileAssets>().add_systems(
+            Update,
+            spawn_projectile_visuals
+                .run_if(in_state(GameState::InGame).and(in_state(PauseState::Unpaused))),
+        );
+    }
+}
+
+/// A fireball. Hurts the player on contact, and can be shot down.
+#[derive(Debug, Component)]
+#[require(
+    Visibility,
+    Health::new(1.0),
+    DeathBehavior = DeathBehavior::Explode {
+        scale: 0.4,
+        damage: 10.0,
+        falloff: Falloff::Quadratic,
+        damage_type: DamageType::Magic,
+    },
+    RigidBody::KinematicPositionBased,
+    Collider::ball(0.3),
+    Sensor,
+    ActiveEvents::COLLISION_EVENTS,
+    CollisionGroups::new(PROJECTILE_GROUP, PLAYER_GROUP | WEAPON_GROUP | EXPLOSION_GROUP),
+)]
+pub struct Projectile {
+    pub velocity: Vec3,
+    pub damage: f32,
+    /// Despawned quietly once this runs out.
+    pub lifetime: Timer,
+}
+
+/// How an enemy shoots, see [`ProjectileLauncher`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RangedAttack {
+    /// Seconds between shots.
+    pub interval: f32,
+    pub projectile_speed: f32,
+    pub damage: f32,
+}
+
+/// Fires a [`Projectile`] at the player every [`RangedAttack::interval`] seconds.
+#[derive(Debug, Component)]
+pub struct ProjectileLauncher {
+    pub attack: RangedAttack,
+    timer: Timer,
+}
+
+impl ProjectileLauncher {
+    pub fn new(attack: RangedAttack) -> Self {
+        ProjectileLauncher {
+            attack,
+            timer: Timer::from_seconds(attack.interval, TimerMode::Repeating),
+        }
+    }
+}
+
+fn fire_projectiles(
+    mut commands: Commands,
+    time: Res<Time>,
+    player: Single<(&GlobalTransform, &Player)>,
+    // primed spitters are about to blow up, and don't get a last shot off
+    mut query: Query<(&GlobalTransform, &mut ProjectileLauncher), Without<Fuse>>,
+) {
+    let (player_transform, player) = *player;
+    if player.dead {
+        return;
+    }
+    let target = player_transform.translation();
+    for (transform, mut launcher) in query.iter_mut() {
+        launcher.timer.tick(time.delta());
+        if !launcher.timer.just_finished() {
+            continue;
+        }
+
+        let pos = transform.translation();
+        let dir = (target - pos).with_y(0.0).normalize_or_zero();
+        commands.spawn((
+            Projectile {
+                velocity: dir * launcher.attack.projectile_speed,
+                damage: launcher.attack.damage,
+                lifetime: Timer::from_seconds(6.0, TimerMode::Once),
+            },
+            // start outside the shooter's collider
+            Transform::from_translation(pos + dir * 0.8),
+            StateScoped(GameState::InGame),
+        ));
+    }
+}
+
+fn move_projectiles(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut Transform, &mut Projectile), Without<Fuse>>,
+) {
+    for (entity, mut transform, mut projectile) in query.iter_mut() {
+        projectile.lifetime.tick(time.delta());
+        if projectile.lifetime.finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        transform.translation += projectile.velocity * time.delta_secs();
+    }
+}
+
+fn projectile_collision(
+    time: Res<Time>,
+    mut writer: EventWriter<DamageEvent>,
+    mut collisions: EventReader<CollisionEvent>,
+    mut chains: ResMut<ChainTracker>,
+    projectile_query: Query<(&GlobalTransform, &Projectile), Without<Fuse>>,
+    player_query: Query<Entity, With<Player>>,
+) {
+    for ev in collisions.read() {
+        if let &CollisionEvent::Started(e1, e2, _flags) = ev {
+            let (player, projectile) = if player_query.contains(e1) {
+                (e1, e2)
+            } else if player_query.contains(e2) {
+                (e2, e1)
+            } else {
+                continue;
+            };
+            let Ok((transform, projectile_data)) = projectile_query.get(projectile) else {
+                continue;
+            };
+
+            let chain_id = chains.start(
+                ChainRoot::Contact {
+                    pos: transform.translation(),
+                },
+                time.elapsed(),
+            );
+            writer.write(DamageEvent {
+                entity: player,
+                damage: projectile_data.damage,
+                damage_type: DamageType::Fire,
+                chain: 0,
+                chain_id,
+            });
+            // burst on impact
+            writer.write(DamageEvent {
+                entity: projectile,
+                damage: f32::INFINITY,
+                damage_type: DamageType::Kinetic,
+                chain: 0,
+                chain_id,
+            });
+        }
+    }
+}
+
+#[derive(Resource, AssetCollection)]
+struct ProjectileAssets {
+    #[asset(path = "textures/flame_fire.dds")]
+    fire_atlas_texture: Handle<Image>,
+    #[asset(texture_atlas_layout(tile_size_x = 128, tile_size_y = 128, columns = 6, rows = 5))]
+    fire_atlas_layout: Handle<TextureAtlasLayout>,
+}
+
+fn spawn_projectile_visuals(
+    mut commands: Commands,
+    assets: Res<ProjectileAssets>,
+    query: Query<Entity, Added<Projectile>>,
+    mut sprite3d_params: Sprite3dParams,
+) {
+    for entity in query.iter() {
+        let atlas = TextureAtlas {
+            layout: assets.fire_atlas_layout.clone(),
+            index: 0,
+        };
+        let animation = AnimatedSprite3d {
+            current: 0,
+            frames: vec![13, 14, 15, 16, 17, 18, 19, 20, 21],
+            timer: Timer::new(Duration::from_millis(80), TimerMode::Repeating),
+            destroy_when_finished: false,
+        };
+        let fire = Sprite3dBuilder {
+            image: assets.fire_atlas_texture.clone(),
+            alpha_mode: AlphaMode::Blend,
+            unlit: true,
+            pixels_per_metre: 128.0,
+            ..Default::default()
+        }
+        .bundle_with_atlas(&mut sprite3d_params, atlas);
+        let visual = commands
+            .spawn((fire, animation, FaceCamera::default()))
+            .id();
+
+        if let Ok(mut c) = commands.get_entity(entity) {
+            c.add_child(visual);
+        } else {
+            commands.entity(visual).despawn();
+        }
+    }
+}
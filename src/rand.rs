@@ -1,11 +1,168 @@
+use std::{fmt, marker::PhantomData, str::FromStr};
+
 use bevy::prelude::*;
 use bevy_rand::prelude::*;
+use rand_core::{RngCore, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::states::GameState;
 
 #[derive(Debug, Default)]
 pub struct RandPlugin;
 
 impl Plugin for RandPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins(EntropyPlugin::<WyRand>::default());
+        app.add_plugins(EntropyPlugin::<WyRand>::default())
+            .init_resource::<RunSeed>()
+            .insert_resource(NextRunSeed(seed_from_launch_args()))
+            .configure_sets(
+                OnEnter(GameState::InGame),
+                (RandSet::ChooseSeed, RandSet::ReseedStreams).chain(),
+            )
+            .add_systems(
+                OnEnter(GameState::InGame),
+                choose_run_seed.in_set(RandSet::ChooseSeed),
+            );
+    }
+}
+
+/// Systems that set up the random number generators at the start of a run.
+///
+/// Anything drawing random numbers in `OnEnter(GameState::InGame)` should run after
+/// [`RandSet::ReseedStreams`].
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RandSet {
+    ChooseSeed,
+    ReseedStreams,
+}
+
+/// The seed all gameplay randomness in the current run is derived from.
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct RunSeed(pub u64);
+
+impl fmt::Display for RunSeed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016X}", self.0)
+    }
+}
+
+impl FromStr for RunSeed {
+    type Err = std::num::ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        u64::from_str_radix(s.trim(), 16).map(RunSeed)
+    }
+}
+
+/// Seed to use for the next run. Consumed when the run starts; if it's empty a random seed is
+/// picked instead.
+#[derive(Resource, Debug, Default, Clone, Copy)]
+pub struct NextRunSeed(pub Option<RunSeed>);
+
+fn choose_run_seed(
+    mut run_seed: ResMut<RunSeed>,
+    mut next_seed: ResMut<NextRunSeed>,
+    mut global: GlobalEntropy<WyRand>,
+) {
+    *run_seed = next_seed
+        .0
+        .take()
+        .unwrap_or_else(|| RunSeed(global.next_u64()));
+    info!("starting run with seed {}", *run_seed);
+}
+
+/// An independent stream of random numbers, used by one system (or a few closely related ones).
+///
+/// Every stream is reseeded from the [`RunSeed`] and its name at the start of a run, so adding
+/// a new stream or drawing more numbers from one doesn't change what the others produce.
+pub trait RngStream: Send + Sync + 'static {
+    const NAME: &'static str;
+}
+
+#[derive(Resource)]
+pub struct StreamRng<S: RngStream> {
+    rng: Entropy<WyRand>,
+    _stream: PhantomData<S>,
+}
+
+impl<S: RngStream> StreamRng<S> {
+    fn from_run_seed(seed: RunSeed) -> Self {
+        StreamRng {
+            rng: Entropy::<WyRand>::seed_from_u64(seed.0 ^ stable_hash(S::NAME)),
+            _stream: PhantomData,
+        }
+    }
+}
+
+impl<S: RngStream> std::ops::Deref for StreamRng<S> {
+    type Target = Entropy<WyRand>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.rng
+    }
+}
+
+impl<S: RngStream> std::ops::DerefMut for StreamRng<S> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.rng
+    }
+}
+
+// FNV-1a, so stream seeds don't depend on the compiler or platform
+const fn stable_hash(name: &str) -> u64 {
+    let bytes = name.as_bytes();
+    let mut hash: u64 = 0xcbf29ce484222325;
+    let mut i = 0;
+    while i < bytes.len() {
+        hash ^= bytes[i] as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+        i += 1;
+    }
+    hash
+}
+
+fn reseed_stream<S: RngStream>(mut commands: Commands, seed: Res<RunSeed>) {
+    commands.insert_resource(StreamRng::<S>::from_run_seed(*seed));
+}
+
+pub trait RngStreamAppExt {
+    fn add_rng_stream<S: RngStream>(&mut self) -> &mut Self;
+}
+
+impl RngStreamAppExt for App {
+    fn add_rng_stream<S: RngStream>(&mut self) -> &mut Self {
+        self.insert_resource(StreamRng::<S>::from_run_seed(RunSeed::default()))
+            .add_systems(
+                OnEnter(GameState::InGame),
+                reseed_stream::<S>.in_set(RandSet::ReseedStreams),
+            )
+    }
+}
+
+/// Reads `--seed <hex>` from the command line.
+#[cfg(not(target_family = "wasm"))]
+fn seed_from_launch_args() -> Option<RunSeed> {
+    let mut args = std::env::args().skip_while(|arg| arg != "--seed").skip(1);
+    parse_launch_seed(args.next()?.as_str())
+}
+
+/// Reads `?seed=<hex>` from the page URL.
+#[cfg(target_family = "wasm")]
+fn seed_from_launch_args() -> Option<RunSeed> {
+    let search = web_sys::window()?.location().search().ok()?;
+    let value = search
+        .trim_start_matches('?')
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("seed="))?;
+    parse_launch_seed(value)
+}
+
+fn parse_launch_seed(value: &str) -> Option<RunSeed> {
+    match value.parse() {
+        Ok(seed) => Some(seed),
+        Err(e) => {
+            warn!("ignoring invalid seed {value:?}: {e}");
+            None
+        }
     }
 }
@@ -0,0 +1,486 @@
+//! Records the input of every run so it can be watched again.
+//!
+//! The simulation runs in `FixedUpdate`, so a replay stores one frame per fixed tick: how long the
+//! tick was, the state of every gameplay action during it, and how far the player turned. Playback
+//! feeds those durations to the time plugin through [`TimeUpdateStrategy::ManualDuration`], so
+//! every update runs exactly one tick, and writes the actions straight into the [`ActionState`]
+//! and [`AccumulatedInput`] before it. Together with the [`RunSeed`] that reproduces the run.
+
+use std::{fmt, time::Duration};
+
+use bevy::{prelude::*, time::TimeUpdateStrategy};
+use leafwing_input_manager::prelude::*;
+
+use crate::{
+    character_controller::AccumulatedInput,
+    game_mode::GameMode,
+    input::{InputAction, InputSettings, InputState},
+    rand::{NextRunSeed, RandSet, RunSeed},
+    states::{GameState, PauseState},
+    storage,
+};
+
+#[derive(Debug, Default)]
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(GameState::InGame),
+            start_recording
+                .after(RandSet::ChooseSeed)
+                .run_if(not(resource_exists::<ReplayPlayback>)),
+        )
+        .add_systems(
+            PreUpdate,
+            abort_playback
+                .run_if(resource_exists::<ReplayPlayback>)
+                .in_set(InputManagerSystem::ManualControl),
+        )
+        .add_systems(
+            FixedPreUpdate,
+            (
+                record_frame.run_if(
+                    in_state(GameState::InGame)
+                        .and(resource_exists::<Recording>)
+                        .and(not(resource_exists::<ReplayPlayback>)),
+                ),
+                apply_playback_frame
+                    .run_if(in_state(GameState::InGame).and(resource_exists::<ReplayPlayback>)),
+            ),
+        )
+        .add_systems(
+            Last,
+            (
+                begin_playback.run_if(resource_added::<ReplayPlayback>),
+                advance_playback.run_if(
+                    resource_exists::<ReplayPlayback>.and(not(resource_added::<ReplayPlayback>)),
+                ),
+            )
+                .chain(),
+        );
+    }
+}
+
+/// Actions stored in a replay, in the order of their bits in [`ReplayFrame::buttons`]. New
+/// actions go on the end, and retired ones leave a gap, so older replays still read the same.
+const RECORDED_BUTTONS: [Option<InputAction>; 21] = [
+    Some(InputAction::MoveForward),
+    Some(InputAction::MoveBackward),
+    Some(InputAction::StrafeLeft),
+    Some(InputAction::StrafeRight),
+    Some(InputAction::TurnLeft),
+    Some(InputAction::TurnRight),
+    Some(InputAction::FireMouse),
+    Some(InputAction::FireSpace),
+    // was Pause, which would freeze playback on the pause menu
+    None,
+    Some(InputAction::AltFireMouse),
+    Some(InputAction::AltFireKey),
+    Some(InputAction::NextWeapon),
+    Some(InputAction::PreviousWeapon),
+    Some(InputAction::SelectWeapon1),
+    Some(InputAction::SelectWeapon2),
+    Some(InputAction::SelectWeapon3),
+    // were ShopBuy1-3, see `ReplayFrame::move_shop_buttons`
+    None,
+    None,
+    None,
+    Some(InputAction::LeaveShop),
+    Some(InputAction::Dash),
+];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ReplayFrame {
+    pub delta: Duration,
+    pub buttons: u32,
+    /// Turns made on this tick, already scaled by the recorder's [`InputSettings`].
+    pub turn: f32,
+}
+
+impl ReplayFrame {
+    fn pressed(&self, index: usize) -> bool {
+        self.buttons & (1 << index) != 0
+    }
+
+    fn pressed_action(&self, action: InputAction) -> bool {
+        RECORDED_BUTTONS
+            .iter()
+            .position(|recorded| *recorded == Some(action))
+            .is_some_and(|index| self.pressed(index))
+    }
+
+    /// The shop used to have buttons of its own, bound to the same keys as SelectWeapon1-3 but
+    /// pressed alone when an offer was clicked. It reads SelectWeapon1-3 now, so moves them there.
+    fn move_shop_buttons(&mut self) {
+        const SHOP_BUY: u32 = 0b111 << 16;
+        // SelectWeapon1-3 are the three bits below
+        self.buttons = (self.buttons & !SHOP_BUY) | ((self.buttons & SHOP_BUY) >> 3);
+    }
+
+    /// Before version 4 `turn` was the raw mouse axis, and turning with the keys was left to the
+    /// buttons. Scales both the way the default [`InputSettings`] would have.
+    fn upgrade_turn(&mut self) {
+        let settings = InputSettings::default();
+        let tick = Time::<Fixed>::default().timestep().as_secs_f32();
+        let mut turn = -self.turn * settings.mouse_sensitivity * tick;
+        if self.pressed_action(InputAction::TurnLeft) {
+            turn += self.delta.as_secs_f32();
+        }
+        if self.pressed_action(InputAction::TurnRight) {
+            turn -= self.delta.as_secs_f32();
+        }
+        self.turn = turn * settings.turn_rate;
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Replay {
+    pub seed: RunSeed,
+    pub mode: GameMode,
+    pub frames: Vec<ReplayFrame>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplayError {
+    BadMagic,
+    UnsupportedVersion(u8),
+    UnknownGameMode(u8),
+    Truncated,
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::BadMagic => write!(f, "not a replay file"),
+            ReplayError::UnsupportedVersion(v) => write!(f, "unsupported replay version {v}"),
+            ReplayError::UnknownGameMode(m) => write!(f, "unknown game mode {m}"),
+            ReplayError::Truncated => write!(f, "replay file is truncated"),
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl Replay {
+    const MAGIC: &'static [u8; 4] = b"ESRP";
+    /// Version 2 added the game mode; version 1 replays are all endless runs. Version 3 widened
+    /// the buttons to 32 bits, and version 4 stores turns already scaled by the input settings.
+    const VERSION: u8 = 4;
+    const HEADER_SIZE: usize = 4 + 1 + 8 + 1 + 4;
+    const V1_HEADER_SIZE: usize = 4 + 1 + 8 + 4;
+    const FRAME_SIZE: usize = 4 + 4 + 4;
+    const V2_FRAME_SIZE: usize = 4 + 2 + 4;
+
+    /// Prefix of the [`storage`] keys replays are saved under.
+    pub const KEY_PREFIX: &'static str = "replay-";
+
+    pub fn duration(&self) -> Duration {
+        self.frames.iter().map(|frame| frame.delta).sum()
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes =
+            Vec::with_capacity(Self::HEADER_SIZE + Self::FRAME_SIZE * self.frames.len());
+        bytes.extend_from_slice(Self::MAGIC);
+        bytes.push(Self::VERSION);
+        bytes.extend_from_slice(&self.seed.0.to_le_bytes());
+        bytes.push(self.mode as u8);
+        bytes.extend_from_slice(&(self.frames.len() as u32).to_le_bytes());
+        for frame in self.frames.iter() {
+            let micros = frame.delta.as_micros().min(u32::MAX as u128) as u32;
+            bytes.extend_from_slice(&micros.to_le_bytes());
+            bytes.extend_from_slice(&frame.buttons.to_le_bytes());
+            bytes.extend_from_slice(&frame.turn.to_le_bytes());
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ReplayError> {
+        let prefix = bytes.get(..5).ok_or(ReplayError::Truncated)?;
+        if &prefix[0..4] != Self::MAGIC {
+            return Err(ReplayError::BadMagic);
+        }
+        let version = prefix[4];
+        let (header_size, frame_size) = match version {
+            1 => (Self::V1_HEADER_SIZE, Self::V2_FRAME_SIZE),
+            2 => (Self::HEADER_SIZE, Self::V2_FRAME_SIZE),
+            3 | Self::VERSION => (Self::HEADER_SIZE, Self::FRAME_SIZE),
+            v => return Err(ReplayError::UnsupportedVersion(v)),
+        };
+        let header = bytes.get(..header_size).ok_or(ReplayError::Truncated)?;
+        let seed = u64::from_le_bytes(header[5..13].try_into().unwrap());
+        let (mode, count) = if version == 1 {
+            (GameMode::Endless, &header[13..17])
+        } else {
+            let mode = GameMode::try_from(header[13]).map_err(ReplayError::UnknownGameMode)?;
+            (mode, &header[14..18])
+        };
+        let count = u32::from_le_bytes(count.try_into().unwrap()) as usize;
+
+        let body = &bytes[header_size..];
+        // a frame count too big to even add up can't have all of its frames there either
+        let body_size = count
+            .checked_mul(frame_size)
+            .ok_or(ReplayError::Truncated)?;
+        if body.len() < body_size {
+            return Err(ReplayError::Truncated);
+        }
+        let frames = body
+            .chunks_exact(frame_size)
+            .take(count)
+            .map(|chunk| {
+                let (buttons, turn) = chunk[4..].split_at(frame_size - 8);
+                let buttons = if frame_size == Self::V2_FRAME_SIZE {
+                    u16::from_le_bytes(buttons.try_into().unwrap()) as u32
+                } else {
+                    u32::from_le_bytes(buttons.try_into().unwrap())
+                };
+                let mut frame = ReplayFrame {
+                    delta: Duration::from_micros(
+                        u32::from_le_bytes(chunk[0..4].try_into().unwrap()) as u64,
+                    ),
+                    buttons,
+                    turn: f32::from_le_bytes(turn.try_into().unwrap()),
+                };
+                if version < Self::VERSION {
+                    frame.upgrade_turn();
+                }
+                frame.move_shop_buttons();
+                frame
+            })
+            .collect();
+
+        Ok(Replay {
+            seed: RunSeed(seed),
+            mode,
+            frames,
+        })
+    }
+
+    /// Saves under the next free `replay-NNNN` key, and returns that key.
+    pub fn save(&self) -> std::io::Result<String> {
+        let next = storage::keys()?
+            .iter()
+            .filter_map(|key| key.strip_prefix(Self::KEY_PREFIX)?.parse::<u32>().ok())
+            .max()
+            .map_or(1, |n| n + 1);
+        let key = format!("{}{next:04}", Self::KEY_PREFIX);
+        storage::save(&key, &self.to_bytes())?;
+        Ok(key)
+    }
+
+    /// Loads every saved replay that can still be read, sorted by key.
+    pub fn load_all() -> Vec<(String, Replay)> {
+        let keys = match storage::keys() {
+            Ok(keys) => keys,
+            Err(e) => {
+                warn!("failed to list saved replays: {e}");
+                return Vec::new();
+            }
+        };
+        let mut replays: Vec<_> = keys
+            .into_iter()
+            .filter(|key| key.starts_with(Self::KEY_PREFIX))
+            .filter_map(|key| match storage::load(&key) {
+                Ok(Some(bytes)) => match Replay::from_bytes(&bytes) {
+                    Ok(replay) => Some((key, replay)),
+                    Err(e) => {
+                        warn!("skipping replay {key}: {e}");
+                        None
+                    }
+                },
+                Ok(None) => None,
+                Err(e) => {
+                    warn!("failed to load replay {key}: {e}");
+                    None
+                }
+            })
+            .collect();
+        replays.sort_by(|(a, _), (b, _)| a.cmp(b));
+        replays
+    }
+}
+
+/// The replay of the current (or most recently finished) run.
+#[derive(Debug, Default, Resource)]
+pub struct Recording(pub Replay);
+
+/// Insert this to watch a replay. Starts a new run with the replay's seed and drives it with the
+/// recorded input until the frames run out or [`InputAction::Pause`] is pressed.
+#[derive(Resource)]
+pub struct ReplayPlayback {
+    replay: Replay,
+    cursor: usize,
+    // stashed while playing back, so leafwing doesn't overwrite the recorded actions with live
+    // input, and time doesn't advance with the wall clock. The actions that aren't recorded stay
+    // bound, so the replay can still be stopped
+    input_map: Option<InputMap<InputAction>>,
+    time_strategy: TimeUpdateStrategy,
+    // the mode picked on the menu, put back afterwards
+    game_mode: GameMode,
+}
+
+impl ReplayPlayback {
+    pub fn new(replay: Replay) -> Self {
+        ReplayPlayback {
+            replay,
+            cursor: 0,
+            input_map: None,
+            time_strategy: TimeUpdateStrategy::Automatic,
+            game_mode: GameMode::default(),
+        }
+    }
+
+    pub fn progress(&self) -> f32 {
+        self.cursor as f32 / self.replay.frames.len().max(1) as f32
+    }
+}
+
+fn start_recording(mut commands: Commands, seed: Res<RunSeed>, mode: Res<GameMode>) {
+    commands.insert_resource(Recording(Replay {
+        seed: *seed,
+        mode: *mode,
+        frames: Vec::new(),
+    }));
+}
+
+fn record_frame(
+    time: Res<Time<Fixed>>,
+    input: Res<ActionState<InputAction>>,
+    input_state: Res<InputState>,
+    accumulated: Res<AccumulatedInput>,
+    mut recording: ResMut<Recording>,
+) {
+    let mut buttons = 0;
+    for (i, action) in RECORDED_BUTTONS.iter().enumerate() {
+        let Some(action) = action else {
+            continue;
+        };
+        // mouse input only counts while the cursor is captured, so bake that in
+        if matches!(action, InputAction::FireMouse | InputAction::AltFireMouse)
+            && !input_state.locked_cursor
+        {
+            continue;
+        }
+        if input.pressed(action) {
+            buttons |= 1 << i;
+        }
+    }
+
+    recording.0.frames.push(ReplayFrame {
+        delta: time.delta(),
+        buttons,
+        turn: accumulated.turn,
+    });
+}
+
+fn begin_playback(
+    mut commands: Commands,
+    mut playback: ResMut<ReplayPlayback>,
+    mut time_strategy: ResMut<TimeUpdateStrategy>,
+    mut next_seed: ResMut<NextRunSeed>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut mode: ResMut<GameMode>,
+    input_map: Option<Res<InputMap<InputAction>>>,
+) {
+    let Some(first) = playback.replay.frames.first().copied() else {
+        warn!("tried to play back an empty replay");
+        commands.remove_resource::<ReplayPlayback>();
+        return;
+    };
+
+    info!(
+        "playing back replay with seed {} ({} frames)",
+        playback.replay.seed,
+        playback.replay.frames.len()
+    );
+    if let Some(input_map) = input_map {
+        let mut live = input_map.clone();
+        for action in RECORDED_BUTTONS.iter().flatten() {
+            live.clear_action(action);
+        }
+        live.clear_action(&InputAction::TurnAxis);
+        playback.input_map = Some(input_map.clone());
+        commands.insert_resource(live);
+    }
+    playback.time_strategy = std::mem::replace(
+        &mut *time_strategy,
+        TimeUpdateStrategy::ManualDuration(first.delta),
+    );
+
+    playback.game_mode = std::mem::replace(&mut *mode, playback.replay.mode);
+
+    commands.remove_resource::<Recording>();
+    next_seed.0 = Some(playback.replay.seed);
+    next_state.set(GameState::InGame);
+}
+
+fn apply_playback_frame(
+    mut playback: ResMut<ReplayPlayback>,
+    mut input: ResMut<ActionState<InputAction>>,
+    mut input_state: ResMut<InputState>,
+    mut accumulated: ResMut<AccumulatedInput>,
+) {
+    let Some(frame) = playback.replay.frames.get(playback.cursor).copied() else {
+        return;
+    };
+    playback.cursor += 1;
+
+    for (i, action) in RECORDED_BUTTONS.iter().enumerate() {
+        let Some(action) = action else {
+            continue;
+        };
+        if frame.pressed(i) {
+            input.press(action);
+        } else {
+            input.release(action);
+        }
+    }
+    accumulated.movement = AccumulatedInput::movement_from(&input);
+    accumulated.turn = frame.turn;
+    input_state.locked_cursor = true;
+}
+
+/// Lines the next update up with the next recorded tick.
+fn advance_playback(world: &mut World) {
+    let Some(playback) = world.get_resource::<ReplayPlayback>() else {
+        return;
+    };
+    if let Some(frame) = playback.replay.frames.get(playback.cursor).copied() {
+        world.insert_resource(TimeUpdateStrategy::ManualDuration(frame.delta));
+    } else {
+        info!("replay finished");
+        finish_playback(world);
+    }
+}
+
+fn abort_playback(world: &mut World) {
+    let mut input = world.resource_mut::<ActionState<InputAction>>();
+    if !input.just_pressed(&InputAction::Pause) {
+        return;
+    }
+    // so it doesn't pause the game as well
+    input.release(&InputAction::Pause);
+
+    info!("replay aborted");
+    finish_playback(world);
+    world
+        .resource_mut::<NextState<GameState>>()
+        .set(GameState::MainMenu);
+    world
+        .resource_mut::<NextState<PauseState>>()
+        .set(PauseState::Unpaused);
+}
+
+/// Hands time and input back to the player.
+fn finish_playback(world: &mut World) {
+    let Some(playback) = world.remove_resource::<ReplayPlayback>() else {
+        return;
+    };
+    world.insert_resource(playback.time_strategy);
+    world.insert_resource(playback.game_mode);
+    if let Some(input_map) = playback.input_map {
+        world.insert_resource(input_map);
+    }
+}
@@ -1,6 +1,12 @@
+use std::time::Duration;
+
 use bevy::prelude::*;
 
-use crate::states::GameState;
+use crate::{
+    chain::{ChainFinished, ChainId, ChainTracker},
+    player::{Player, PlayerHurtEvent},
+    states::{GameState, PauseState},
+};
 
 #[derive(Debug, Default)]
 pub struct ScorePlugin;
@@ -8,31 +14,351 @@ pub struct ScorePlugin;
 impl Plugin for ScorePlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<Score>()
+            .init_resource::<RunStats>()
+            .init_resource::<ScoringRules>()
+            .init_resource::<Combo>()
             .add_systems(OnEnter(GameState::InGame), |mut commands: Commands| {
                 commands.insert_resource(Score::default());
+                commands.insert_resource(RunStats::default());
+                commands.insert_resource(Combo::default());
             })
             .add_state_scoped_event::<ScoreEvent>(GameState::InGame)
-            .add_systems(Update, handle_score_event);
+            .add_state_scoped_event::<PointsAwarded>(GameState::InGame)
+            .add_systems(
+                FixedUpdate,
+                (handle_score_event, award_chain_bonus, apply_hurt_penalty),
+            )
+            .add_systems(
+                FixedUpdate,
+                (track_run_time, decay_combo)
+                    .run_if(in_state(GameState::InGame).and(in_state(PauseState::Unpaused))),
+            );
     }
 }
 
 #[derive(Debug, Default, Resource)]
 pub struct Score {
     pub score: u64,
+    /// Points that haven't been spent in the shop yet. Spending doesn't lower the score.
+    pub banked: u64,
+}
+
+impl Score {
+    pub fn add(&mut self, points: u64) {
+        self.score += points;
+        self.banked += points;
+    }
+}
+
+/// Statistics about the current run, kept around after it ends for the end screen.
+#[derive(Debug, Default, Resource)]
+pub struct RunStats {
+    pub longest_chain: u64,
+    pub time_survived: Duration,
+    pub shots_fired: u32,
+    /// Shots that hit a skull.
+    pub shots_hit: u32,
+}
+
+impl RunStats {
+    /// Fraction of shots that hit something, or `None` if nothing was fired.
+    pub fn accuracy(&self) -> Option<f32> {
+        if self.shots_fired == 0 {
+            return None;
+        }
+        Some(self.shots_hit as f32 / self.shots_fired as f32)
+    }
 }
 
+/// Base points for killing this, before chain and combo bonuses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Component)]
+pub struct KillScore(pub u64);
+
 #[derive(Debug, Event)]
 pub struct ScoreEvent {
+    /// Where the kill happened.
+    pub pos: Vec3,
+    /// The [`KillScore`] of whatever was killed.
+    pub value: u64,
+    pub chain: u64,
+    pub chain_id: ChainId,
+}
+
+/// Sent for every kill once its points have been worked out.
+#[derive(Debug, Clone, Copy, Event)]
+pub struct PointsAwarded {
+    pub pos: Vec3,
+    pub points: u64,
     pub chain: u64,
 }
 
-pub const SCORE_PER_SKULL: u64 = 150;
-pub const SCORE_PER_CHAIN: u64 = 60;
+/// How points are awarded. Game modes can replace this resource to change the rules.
+#[derive(Debug, Clone, Resource)]
+pub struct ScoringRules {
+    /// Extra points per link of the chain the kill happened in.
+    pub per_chain_depth: u64,
+
+    /// Kills less than this many seconds apart keep the combo going.
+    pub combo_window: f32,
+    /// Added to the multiplier for every kill in a combo.
+    pub combo_step: f32,
+    pub max_multiplier: f32,
+    /// Multiplier lost per second once the combo window has passed.
+    pub combo_decay: f32,
+
+    /// Bonus per kill in a chain, awarded when the chain finishes.
+    pub chain_bonus_per_kill: u64,
+    /// Chains with fewer kills than this don't get a bonus.
+    pub chain_bonus_min_kills: u32,
+
+    /// Points lost when the player gets hurt.
+    pub hurt_penalty: u64,
+    /// Whether getting hurt drops the multiplier back to 1.
+    pub hurt_resets_combo: bool,
+}
 
-fn handle_score_event(mut score: ResMut<Score>, mut reader: EventReader<ScoreEvent>) {
+impl Default for ScoringRules {
+    fn default() -> Self {
+        ScoringRules {
+            per_chain_depth: 60,
+            combo_window: 2.0,
+            combo_step: 0.1,
+            max_multiplier: 5.0,
+            combo_decay: 1.0,
+            chain_bonus_per_kill: 100,
+            chain_bonus_min_kills: 3,
+            hurt_penalty: 500,
+            hurt_resets_combo: true,
+        }
+    }
+}
+
+impl ScoringRules {
+    /// Points for a kill worth `value` on its own.
+    pub fn kill_points(&self, value: u64, chain: u64, multiplier: f32) -> u64 {
+        let base = value + self.per_chain_depth * chain;
+        (base as f32 * multiplier).round() as u64
+    }
+
+    pub fn chain_bonus(&self, kills: u32) -> u64 {
+        if kills < self.chain_bonus_min_kills {
+            return 0;
+        }
+        self.chain_bonus_per_kill * kills as u64
+    }
+}
+
+#[derive(Debug, Resource)]
+pub struct Combo {
+    pub multiplier: f32,
+    pub since_last_kill: f32,
+}
+
+impl Default for Combo {
+    fn default() -> Self {
+        Combo {
+            multiplier: 1.0,
+            since_last_kill: 0.0,
+        }
+    }
+}
+
+fn handle_score_event(
+    mut score: ResMut<Score>,
+    mut stats: ResMut<RunStats>,
+    mut chains: ResMut<ChainTracker>,
+    mut combo: ResMut<Combo>,
+    rules: Res<ScoringRules>,
+    mut reader: EventReader<ScoreEvent>,
+    mut writer: EventWriter<PointsAwarded>,
+) {
     let mut total = 0;
-    for ScoreEvent { chain } in reader.read() {
-        total += SCORE_PER_SKULL + SCORE_PER_CHAIN * chain
+    for ScoreEvent {
+        pos,
+        value,
+        chain,
+        chain_id,
+    } in reader.read()
+    {
+        let points = rules.kill_points(*value, *chain, combo.multiplier);
+        total += points;
+        chains.record_kill(*chain_id, points);
+        writer.write(PointsAwarded {
+            pos: *pos,
+            points,
+            chain: *chain,
+        });
+        // a skull killed by the shotgun is a chain of one
+        stats.longest_chain = stats.longest_chain.max(chain + 1);
+
+        combo.multiplier = (combo.multiplier + rules.combo_step).min(rules.max_multiplier);
+        combo.since_last_kill = 0.0;
+    }
+    score.add(total);
+}
+
+fn decay_combo(time: Res<Time>, mut combo: ResMut<Combo>, rules: Res<ScoringRules>) {
+    combo.since_last_kill += time.delta_secs();
+    if combo.since_last_kill > rules.combo_window && combo.multiplier > 1.0 {
+        combo.multiplier = (combo.multiplier - rules.combo_decay * time.delta_secs()).max(1.0);
+    }
+}
+
+fn award_chain_bonus(
+    mut score: ResMut<Score>,
+    rules: Res<ScoringRules>,
+    mut reader: EventReader<ChainFinished>,
+) {
+    for ChainFinished { stats, .. } in reader.read() {
+        score.add(rules.chain_bonus(stats.kills));
+    }
+}
+
+fn apply_hurt_penalty(
+    mut score: ResMut<Score>,
+    mut combo: ResMut<Combo>,
+    rules: Res<ScoringRules>,
+    mut reader: EventReader<PlayerHurtEvent>,
+) {
+    for _ in reader.read() {
+        score.score = score.score.saturating_sub(rules.hurt_penalty);
+        score.banked = score.banked.saturating_sub(rules.hurt_penalty);
+        if rules.hurt_resets_combo {
+            combo.multiplier = 1.0;
+        }
+    }
+}
+
+fn track_run_time(time: Res<Time>, mut stats: ResMut<RunStats>, player: Single<&Player>) {
+    if player.dead {
+        return;
+    }
+    stats.time_survived += time.delta();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chain::{ChainRoot, ChainStats};
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.init_resource::<Score>()
+            .init_resource::<RunStats>()
+            .init_resource::<ScoringRules>()
+            .init_resource::<Combo>()
+            .init_resource::<ChainTracker>()
+            .init_resource::<Time>()
+            .add_event::<ScoreEvent>()
+            .add_event::<PointsAwarded>()
+            .add_event::<ChainFinished>()
+            .add_systems(
+                Update,
+                (handle_score_event, award_chain_bonus, decay_combo).chain(),
+            );
+        app
+    }
+
+    fn step(app: &mut App, seconds: f32) {
+        app.world_mut()
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_secs_f32(seconds));
+        app.update();
+    }
+
+    fn kill(app: &mut App, value: u64, chain: u64, chain_id: ChainId) {
+        app.world_mut().send_event(ScoreEvent {
+            pos: Vec3::ZERO,
+            value,
+            chain,
+            chain_id,
+        });
+    }
+
+    fn finish_chain(app: &mut App, kills: u32) {
+        app.world_mut().send_event(ChainFinished {
+            id: ChainId::default(),
+            stats: ChainStats {
+                root: ChainRoot::Shot { origin: Vec3::ZERO },
+                depth: 0,
+                kills,
+                score: 0,
+                started: Duration::ZERO,
+                last_activity: Duration::ZERO,
+            },
+        });
+    }
+
+    #[test]
+    fn kills_build_the_combo_and_pay_for_chain_depth() {
+        let mut app = app();
+        let chain_id = app
+            .world_mut()
+            .resource_mut::<ChainTracker>()
+            .start(ChainRoot::Shot { origin: Vec3::ZERO }, Duration::ZERO);
+        kill(&mut app, 100, 0, chain_id);
+        kill(&mut app, 100, 2, chain_id);
+        step(&mut app, 0.0);
+
+        // 100 at x1.0, then 100 + 2 * 60 at x1.1
+        let score = app.world().resource::<Score>();
+        assert_eq!(score.score, 342);
+        assert_eq!(score.banked, 342);
+        assert!((app.world().resource::<Combo>().multiplier - 1.2).abs() < 1e-5);
+        assert_eq!(app.world().resource::<RunStats>().longest_chain, 3);
+
+        let stats = app
+            .world()
+            .resource::<ChainTracker>()
+            .get(chain_id)
+            .unwrap();
+        assert_eq!(stats.kills, 2);
+        assert_eq!(stats.score, 342);
+
+        let awarded: Vec<_> = app
+            .world()
+            .resource::<Events<PointsAwarded>>()
+            .iter_current_update_events()
+            .map(|awarded| (awarded.points, awarded.chain))
+            .collect();
+        assert_eq!(awarded, [(100, 0), (242, 2)]);
+    }
+
+    #[test]
+    fn combo_is_capped() {
+        let mut app = app();
+        app.world_mut().resource_mut::<Combo>().multiplier = 4.95;
+        kill(&mut app, 100, 0, ChainId::default());
+        step(&mut app, 0.0);
+        assert_eq!(app.world().resource::<Combo>().multiplier, 5.0);
+    }
+
+    #[test]
+    fn combo_decays_once_the_window_passes() {
+        let mut app = app();
+        app.world_mut().resource_mut::<Combo>().multiplier = 2.0;
+
+        step(&mut app, 1.5);
+        assert_eq!(app.world().resource::<Combo>().multiplier, 2.0);
+
+        // past the 2 second window, losing 1 per second
+        step(&mut app, 0.75);
+        assert!((app.world().resource::<Combo>().multiplier - 1.25).abs() < 1e-5);
+
+        // but never below 1
+        step(&mut app, 1.0);
+        assert_eq!(app.world().resource::<Combo>().multiplier, 1.0);
+    }
+
+    #[test]
+    fn chain_bonus_needs_enough_kills() {
+        let mut app = app();
+        finish_chain(&mut app, 2);
+        step(&mut app, 0.0);
+        assert_eq!(app.world().resource::<Score>().score, 0);
+
+        finish_chain(&mut app, 3);
+        step(&mut app, 0.0);
+        assert_eq!(app.world().resource::<Score>().score, 300);
     }
-    score.score += total;
 }
@@ -0,0 +1,131 @@
+use bevy::prelude::*;
+use bevy_asset_loader::asset_collection::AssetCollection;
+
+use crate::{
+    assets::AssetLoadingExt,
+    camera::MainCamera,
+    score::PointsAwarded,
+    states::{GameState, PauseState},
+};
+
+#[derive(Debug, Default)]
+pub struct ScorePopupPlugin;
+
+impl Plugin for ScorePopupPlugin {
+    fn build(&self, app: &mut App) {
+        app.load_asset_on_startup::<ScorePopupAssets>()
+            .add_systems(Update, spawn_score_popups)
+            .add_systems(
+                Update,
+                age_score_popups
+                    .run_if(in_state(GameState::InGame).and(in_state(PauseState::Unpaused))),
+            )
+            .add_systems(
+                PostUpdate,
+                place_score_popups.after(TransformSystem::TransformPropagate),
+            );
+    }
+}
+
+#[derive(Resource, AssetCollection)]
+struct ScorePopupAssets {
+    #[asset(path = "fonts/Bore Blasters 21.ttf")]
+    font: Handle<Font>,
+}
+
+/// Points shown floating above where a kill happened.
+#[derive(Debug, Component)]
+struct ScorePopup {
+    world_pos: Vec3,
+    age: f32,
+}
+
+impl ScorePopup {
+    const LIFETIME: f32 = 1.2;
+    /// World units per second.
+    const RISE_SPEED: f32 = 3.0;
+}
+
+fn popup_style(chain: u64) -> (f32, Color) {
+    let font_size = 28.0 + 6.0 * chain.min(6) as f32;
+    let color = match chain {
+        0 => Color::WHITE,
+        1 => Color::srgb(1.0, 0.85, 0.2),
+        2 => Color::srgb(1.0, 0.55, 0.1),
+        3 => Color::srgb(1.0, 0.2, 0.1),
+        _ => Color::srgb(1.0, 0.2, 0.8),
+    };
+    (font_size, color)
+}
+
+fn spawn_score_popups(
+    mut commands: Commands,
+    assets: Res<ScorePopupAssets>,
+    mut reader: EventReader<PointsAwarded>,
+) {
+    for PointsAwarded { pos, points, chain } in reader.read() {
+        let (font_size, color) = popup_style(*chain);
+        // kills further down a chain also show how deep they were
+        let text = if *chain > 0 {
+            format!("+{points} (chain {chain})")
+        } else {
+            format!("+{points}")
+        };
+        commands.spawn((
+            ScorePopup {
+                world_pos: *pos,
+                age: 0.0,
+            },
+            Text::new(text),
+            TextFont {
+                font: assets.font.clone(),
+                font_size,
+                ..Default::default()
+            },
+            TextColor(color),
+            TextShadow::default(),
+            Node {
+                position_type: PositionType::Absolute,
+                ..Default::default()
+            },
+            // hidden until it's been placed on screen
+            Visibility::Hidden,
+            StateScoped(GameState::InGame),
+        ));
+    }
+}
+
+fn age_score_popups(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut ScorePopup, &mut TextColor)>,
+) {
+    for (entity, mut popup, mut color) in query.iter_mut() {
+        popup.age += time.delta_secs();
+        if popup.age >= ScorePopup::LIFETIME {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        popup.world_pos.y += ScorePopup::RISE_SPEED * time.delta_secs();
+        color.0.set_alpha(1.0 - popup.age / ScorePopup::LIFETIME);
+    }
+}
+
+fn place_score_popups(
+    camera: Single<(&Camera, &GlobalTransform), With<MainCamera>>,
+    mut query: Query<(&ScorePopup, &ComputedNode, &mut Node, &mut Visibility)>,
+) {
+    let (camera, camera_transform) = *camera;
+    for (popup, computed, mut node, mut visibility) in query.iter_mut() {
+        let Ok(screen_pos) = camera.world_to_viewport(camera_transform, popup.world_pos) else {
+            // behind the camera
+            *visibility = Visibility::Hidden;
+            continue;
+        };
+        // centre the text on the kill
+        let half_size = computed.size() * computed.inverse_scale_factor() / 2.0;
+        node.left = Val::Px(screen_pos.x - half_size.x);
+        node.top = Val::Px(screen_pos.y - half_size.y);
+        *visibility = Visibility::Inherited;
+    }
+}
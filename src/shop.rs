@@ -0,0 +1,339 @@
+use bevy::{platform::collections::HashMap, prelude::*, window::PrimaryWindow};
+use bevy_egui::{
+    EguiContextPass, EguiContexts,
+    egui::{self, Align2, RichText},
+};
+use leafwing_input_manager::prelude::ActionState;
+use rand_core::RngCore;
+
+use crate::{
+    character_controller::CharacterController,
+    health::Health,
+    input::InputAction,
+    player::Player,
+    rand::{RngStream, RngStreamAppExt, StreamRng},
+    replay::ReplayPlayback,
+    score::Score,
+    states::{GameState, PauseState},
+    wave::WaveCleared,
+    weapon::{AltWeapon, Weapon, WeaponInventory},
+};
+
+/// Opens the upgrade shop after every cleared wave, and keeps track of what's been bought.
+#[derive(Debug, Default)]
+pub struct ShopPlugin;
+
+impl Plugin for ShopPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_rng_stream::<ShopRng>()
+            .init_resource::<Upgrades>()
+            .add_state_scoped_event::<UpgradePurchased>(GameState::InGame)
+            .add_systems(OnEnter(GameState::InGame), |mut commands: Commands| {
+                commands.insert_resource(Upgrades::default());
+                commands.remove_resource::<Shop>();
+            })
+            .add_systems(
+                FixedUpdate,
+                open_shop.run_if(in_state(GameState::InGame).and(in_state(PauseState::Unpaused))),
+            )
+            .add_systems(
+                FixedUpdate,
+                shop_input.run_if(
+                    in_state(GameState::InGame)
+                        .and(in_state(PauseState::Shopping))
+                        .and(resource_exists::<Shop>),
+                ),
+            );
+    }
+}
+
+/// The shop window.
+#[derive(Debug, Default)]
+pub struct ShopMenuPlugin;
+
+impl Plugin for ShopMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<QueuedShopAction>()
+            .add_systems(
+                EguiContextPass,
+                shop_menu.run_if(in_state(GameState::InGame).and(in_state(PauseState::Shopping))),
+            )
+            // before the replay records the tick's input
+            .add_systems(
+                FixedFirst,
+                press_queued_shop_action.run_if(not(resource_exists::<ReplayPlayback>)),
+            );
+    }
+}
+
+struct ShopRng;
+
+impl RngStream for ShopRng {
+    const NAME: &'static str = "shop";
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Upgrade {
+    MaxHealth,
+    FasterReload,
+    WiderExplosions,
+    MoreShells,
+    MoveSpeed,
+}
+
+impl Upgrade {
+    pub const ALL: [Upgrade; 5] = [
+        Upgrade::MaxHealth,
+        Upgrade::FasterReload,
+        Upgrade::WiderExplosions,
+        Upgrade::MoreShells,
+        Upgrade::MoveSpeed,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Upgrade::MaxHealth => "Thick Skull",
+            Upgrade::FasterReload => "Quick Hands",
+            Upgrade::WiderExplosions => "Volatile",
+            Upgrade::MoreShells => "Bandolier",
+            Upgrade::MoveSpeed => "Light Feet",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Upgrade::MaxHealth => "+20 max health",
+            Upgrade::FasterReload => "+25% reload speed",
+            Upgrade::WiderExplosions => "+15% explosion radius on kills",
+            Upgrade::MoreShells => "+1 round in every magazine",
+            Upgrade::MoveSpeed => "+10% movement speed",
+        }
+    }
+
+    /// Price of the first one. Every one bought after that costs as much again.
+    fn base_cost(self) -> u64 {
+        match self {
+            Upgrade::MaxHealth | Upgrade::MoveSpeed => 1500,
+            Upgrade::FasterReload | Upgrade::MoreShells => 2000,
+            Upgrade::WiderExplosions => 2500,
+        }
+    }
+}
+
+/// How many of each [`Upgrade`] have been bought this run.
+#[derive(Debug, Default, Clone, Resource)]
+pub struct Upgrades {
+    stacks: HashMap<Upgrade, u32>,
+}
+
+impl Upgrades {
+    pub fn stacks(&self, upgrade: Upgrade) -> u32 {
+        self.stacks.get(&upgrade).copied().unwrap_or(0)
+    }
+
+    pub fn cost(&self, upgrade: Upgrade) -> u64 {
+        upgrade.base_cost() * (self.stacks(upgrade) as u64 + 1)
+    }
+
+    /// Multiplier for how fast weapons reload and recharge.
+    pub fn reload_speed(&self) -> f32 {
+        1.0 + 0.25 * self.stacks(Upgrade::FasterReload) as f32
+    }
+
+    /// Multiplier for the scale of explosions when things die.
+    pub fn explosion_scale(&self) -> f32 {
+        1.0 + 0.15 * self.stacks(Upgrade::WiderExplosions) as f32
+    }
+}
+
+/// Upgrades on sale. Only exists while the shop is open.
+#[derive(Debug, Clone, Resource)]
+pub struct Shop {
+    pub offers: Vec<ShopOffer>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ShopOffer {
+    pub upgrade: Upgrade,
+    pub sold: bool,
+}
+
+impl Shop {
+    const OFFERS: usize = 3;
+    /// The actions that buy each offer, in order. Weapons can't be switched while the shop's
+    /// open, so the weapon keys are free.
+    const BUY_ACTIONS: [InputAction; Self::OFFERS] = [
+        InputAction::SelectWeapon1,
+        InputAction::SelectWeapon2,
+        InputAction::SelectWeapon3,
+    ];
+
+    /// A few different upgrades picked at random.
+    fn new(rng: &mut impl RngCore) -> Self {
+        let mut remaining = Upgrade::ALL.to_vec();
+        let offers = (0..Self::OFFERS.min(remaining.len()))
+            .map(|_| {
+                let index = rng.next_u32() as usize % remaining.len();
+                ShopOffer {
+                    upgrade: remaining.swap_remove(index),
+                    sold: false,
+                }
+            })
+            .collect();
+        Shop { offers }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Event)]
+pub struct UpgradePurchased {
+    pub upgrade: Upgrade,
+}
+
+fn open_shop(
+    mut commands: Commands,
+    mut reader: EventReader<WaveCleared>,
+    mut rng: ResMut<StreamRng<ShopRng>>,
+    mut next_state: ResMut<NextState<PauseState>>,
+    player: Single<&Player>,
+) {
+    if reader.is_empty() {
+        return;
+    }
+    reader.clear();
+    if player.dead {
+        return;
+    }
+    commands.insert_resource(Shop::new(&mut *rng));
+    next_state.set(PauseState::Shopping);
+}
+
+fn shop_input(
+    mut commands: Commands,
+    input: Res<ActionState<InputAction>>,
+    mut shop: ResMut<Shop>,
+    mut score: ResMut<Score>,
+    mut upgrades: ResMut<Upgrades>,
+    mut next_state: ResMut<NextState<PauseState>>,
+    mut writer: EventWriter<UpgradePurchased>,
+    mut player_query: Query<
+        (
+            &mut Health,
+            &mut CharacterController,
+            &mut Weapon,
+            Option<&mut WeaponInventory>,
+            Option<&mut AltWeapon>,
+        ),
+        With<Player>,
+    >,
+) {
+    if input.just_pressed(&InputAction::LeaveShop) {
+        commands.remove_resource::<Shop>();
+        next_state.set(PauseState::Unpaused);
+        return;
+    }
+
+    for (offer, action) in shop.offers.iter_mut().zip(Shop::BUY_ACTIONS.iter()) {
+        if !input.just_pressed(action) || offer.sold {
+            continue;
+        }
+        let cost = upgrades.cost(offer.upgrade);
+        if score.banked < cost {
+            continue;
+        }
+        let Ok((mut health, mut controller, mut weapon, inventory, alt_weapon)) =
+            player_query.single_mut()
+        else {
+            warn!("didn't find exactly one player to upgrade");
+            return;
+        };
+
+        score.banked -= cost;
+        offer.sold = true;
+        *upgrades.stacks.entry(offer.upgrade).or_default() += 1;
+        info!("bought {:?} for {}", offer.upgrade, cost);
+
+        // the rest are read by the systems they affect
+        match offer.upgrade {
+            Upgrade::MaxHealth => {
+                health.max += 20.0;
+                health.current += 20.0;
+            }
+            Upgrade::MoreShells => {
+                let holstered = inventory
+                    .into_iter()
+                    .flat_map(|i| i.into_inner().holstered_mut());
+                let alt = alt_weapon.into_iter().map(|alt| &mut alt.into_inner().0);
+                for weapon in std::iter::once(&mut *weapon).chain(holstered).chain(alt) {
+                    weapon.def.magazine += 1;
+                    weapon.rounds += 1;
+                }
+            }
+            Upgrade::MoveSpeed => {
+                controller.max_speed *= 1.1;
+            }
+            Upgrade::FasterReload | Upgrade::WiderExplosions => {}
+        }
+        writer.write(UpgradePurchased {
+            upgrade: offer.upgrade,
+        });
+    }
+}
+
+/// An action clicked in the shop window, pressed at the start of the next tick so it's handled
+/// (and recorded) like any other input.
+#[derive(Debug, Default, Resource)]
+struct QueuedShopAction(Option<InputAction>);
+
+fn press_queued_shop_action(
+    mut queued: ResMut<QueuedShopAction>,
+    mut input: ResMut<ActionState<InputAction>>,
+) {
+    if let Some(action) = queued.0.take() {
+        input.press(&action);
+    }
+}
+
+fn shop_menu(
+    mut contexts: EguiContexts,
+    main_window: Single<&Window, With<PrimaryWindow>>,
+    shop: Option<Res<Shop>>,
+    score: Res<Score>,
+    upgrades: Res<Upgrades>,
+    mut queued: ResMut<QueuedShopAction>,
+) {
+    let Some(shop) = shop else {
+        return;
+    };
+    egui::Window::new("Shop")
+        .auto_sized()
+        .movable(false)
+        .pivot(Align2::CENTER_CENTER)
+        .default_pos((main_window.size() / 2.0).to_array())
+        .collapsible(false)
+        .show(contexts.ctx_mut(), |ui| {
+            ui.label(format!("Points to spend: {}", score.banked));
+            ui.add_space(5.0);
+            egui::Grid::new("shop_grid").show(ui, |ui| {
+                for (i, (offer, action)) in shop.offers.iter().zip(Shop::BUY_ACTIONS).enumerate() {
+                    let cost = upgrades.cost(offer.upgrade);
+                    ui.label(RichText::new(offer.upgrade.name()).strong());
+                    ui.label(offer.upgrade.description());
+                    ui.label(format!("owned: {}", upgrades.stacks(offer.upgrade)));
+                    let text = if offer.sold {
+                        "Sold".to_string()
+                    } else {
+                        format!("[{}] Buy for {cost}", i + 1)
+                    };
+                    let enabled = !offer.sold && score.banked >= cost;
+                    if ui.add_enabled(enabled, egui::Button::new(text)).clicked() {
+                        queued.0 = Some(action);
+                    }
+                    ui.end_row();
+                }
+            });
+            ui.add_space(5.0);
+            if ui.button("[Enter] Next Wave").clicked() {
+                queued.0 = Some(InputAction::LeaveShop);
+            }
+        });
+}
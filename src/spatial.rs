@@ -0,0 +1,121 @@
+//! A uniform grid of everything with [`Health`], for cheap "what's near here" queries.
+//!
+//! Rebuilt once per fixed tick, so positions can be up to a tick out of date, and entities
+//! spawned since the last rebuild aren't in it yet.
+
+use bevy::{platform::collections::HashMap, prelude::*};
+use bevy_rapier3d::prelude::{CollisionGroups, Group};
+
+use crate::{
+    health::Health,
+    states::{GameState, PauseState},
+};
+
+#[derive(Debug, Default)]
+pub struct SpatialPlugin;
+
+impl Plugin for SpatialPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SpatialIndex>()
+            .add_systems(
+                OnEnter(GameState::InGame),
+                |mut index: ResMut<SpatialIndex>| {
+                    index.clear();
+                },
+            )
+            .add_systems(
+                FixedPreUpdate,
+                rebuild_spatial_index
+                    .run_if(in_state(GameState::InGame).and(in_state(PauseState::Unpaused))),
+            );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpatialEntry {
+    pub entity: Entity,
+    pub pos: Vec3,
+    /// The entity's collision group memberships, or [`Group::ALL`] if it doesn't have any.
+    pub groups: Group,
+}
+
+/// Buckets entities into square cells on the ground plane.
+#[derive(Debug, Resource)]
+pub struct SpatialIndex {
+    cell_size: f32,
+    cells: HashMap<IVec2, Vec<SpatialEntry>>,
+}
+
+impl Default for SpatialIndex {
+    fn default() -> Self {
+        // about the size of the biggest explosions and steering radii
+        SpatialIndex::new(4.0)
+    }
+}
+
+impl SpatialIndex {
+    pub fn new(cell_size: f32) -> Self {
+        SpatialIndex {
+            cell_size,
+            cells: HashMap::default(),
+        }
+    }
+
+    fn cell(&self, pos: Vec3) -> IVec2 {
+        IVec2::new(
+            (pos.x / self.cell_size).floor() as i32,
+            (pos.z / self.cell_size).floor() as i32,
+        )
+    }
+
+    pub fn clear(&mut self) {
+        // keep the allocations around for the next rebuild
+        for entries in self.cells.values_mut() {
+            entries.clear();
+        }
+    }
+
+    pub fn insert(&mut self, entry: SpatialEntry) {
+        let cell = self.cell(entry.pos);
+        self.cells.entry(cell).or_default().push(entry);
+    }
+
+    /// Entries within `radius` of `pos` on the ground plane that are in any of `groups`.
+    pub fn within(
+        &self,
+        pos: Vec3,
+        radius: f32,
+        groups: Group,
+    ) -> impl Iterator<Item = SpatialEntry> + '_ {
+        let min = self.cell(pos - Vec3::splat(radius));
+        let max = self.cell(pos + Vec3::splat(radius));
+        (min.x..=max.x)
+            .flat_map(move |x| (min.y..=max.y).map(move |y| IVec2::new(x, y)))
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .copied()
+            .filter(move |entry| {
+                entry.groups.intersects(groups)
+                    && entry.pos.xz().distance_squared(pos.xz()) <= radius * radius
+            })
+    }
+
+    /// Whether anything in `groups` is within `radius` of `pos`.
+    pub fn any_within(&self, pos: Vec3, radius: f32, groups: Group) -> bool {
+        self.within(pos, radius, groups).next().is_some()
+    }
+}
+
+fn rebuild_spatial_index(
+    mut index: ResMut<SpatialIndex>,
+    query: Query<(Entity, &GlobalTransform, Option<&CollisionGroups>), With<Health>>,
+) {
+    index.clear();
+    for (entity, transform, groups) in query.iter() {
+        index.insert(SpatialEntry {
+            entity,
+            pos: transform.translation(),
+            groups: groups.map_or(Group::ALL, |groups| groups.memberships),
+        });
+    }
+}
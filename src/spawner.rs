@@ -2,13 +2,19 @@ use std::time::Duration;
 
 use bevy::prelude::*;
 use bevy_asset_loader::asset_collection::AssetCollection;
-use bevy_rand::{global::GlobalEntropy, prelude::WyRand};
+use bevy_rand::prelude::{Entropy, WyRand};
 use bevy_sprite3d::{Sprite3dBuilder, Sprite3dParams};
+use rand_core::RngCore;
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    assets::AssetLoadingExt,
+    assets::{AssetLoadingExt, SingleAssetCollection, apply_ron_asset},
+    enemy::EnemyKind,
     fire_skull::FireSkull,
-    player::Player,
+    game_mode::{GameMode, in_game_mode},
+    physics::PLAYER_GROUP,
+    rand::{RngStream, RngStreamAppExt, StreamRng},
+    spatial::SpatialIndex,
     sprite::{AnimatedSprite3d, FaceCamera},
     states::{GameState, PauseState},
 };
@@ -18,36 +24,208 @@ pub struct SpawnerPlugin;
 
 impl Plugin for SpawnerPlugin {
     fn build(&self, app: &mut App) {
-        app.init_resource::<SpawnParameters>()
+        app.register_ron_asset::<SpawnDirector>(&["director.ron"])
+            .init_resource::<SpawnDirector>()
+            .init_resource::<GameMode>()
             .add_state_scoped_event::<CreateSpawnerEvent>(GameState::InGame)
-            .load_asset_on_startup::<SpawnerAssets>()
             .init_resource::<SkullsKilled>()
+            .add_rng_stream::<SpawnerRng>()
             .add_systems(
                 OnEnter(GameState::InGame),
                 (
                     reset_skulls_killed,
                     reset_spawn_parameters,
-                    create_first_spawner,
+                    create_first_spawner.run_if(in_game_mode(GameMode::Endless)),
                 )
                     .chain(),
             )
             .add_systems(
-                Update,
-                (spawn_spawners, run_spawners, create_spawners)
+                FixedUpdate,
+                (
+                    spawn_spawners,
+                    run_spawners,
+                    create_spawners.run_if(in_game_mode(GameMode::Endless)),
+                )
                     .run_if(in_state(GameState::InGame).and(in_state(PauseState::Unpaused))),
             );
     }
 }
 
+/// Loads the [`SpawnDirector`] from `assets/data`, and reloads it whenever the file changes.
+/// Without this the built-in defaults are used.
+#[derive(Debug, Default)]
+pub struct SpawnDirectorAssetPlugin;
+
+impl Plugin for SpawnDirectorAssetPlugin {
+    fn build(&self, app: &mut App) {
+        app.load_asset_on_startup::<SpawnDirectorAssets>()
+            .add_systems(
+                Update,
+                apply_ron_asset::<SpawnDirector, SpawnDirectorAssets>,
+            );
+    }
+}
+
+#[derive(Resource, AssetCollection)]
+struct SpawnDirectorAssets {
+    #[asset(path = "data/spawn.director.ron")]
+    director: Handle<SpawnDirector>,
+}
+
+impl SingleAssetCollection<SpawnDirector> for SpawnDirectorAssets {
+    fn handle(&self) -> &Handle<SpawnDirector> {
+        &self.director
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct SpawnerVisualsPlugin;
+
+impl Plugin for SpawnerVisualsPlugin {
+    fn build(&self, app: &mut App) {
+        app.load_asset_on_startup::<SpawnerAssets>().add_systems(
+            Update,
+            spawn_spawner_visuals
+                .run_if(in_state(GameState::InGame).and(in_state(PauseState::Unpaused))),
+        );
+    }
+}
+
 #[derive(Resource, Default, Debug)]
 pub struct SkullsKilled {
     pub count: usize,
 }
 
+struct SpawnerRng;
+
+impl RngStream for SpawnerRng {
+    const NAME: &'static str = "spawner";
+}
+
 fn reset_skulls_killed(mut commands: Commands) {
     commands.insert_resource(SkullsKilled::default());
 }
 
+/// A value that changes with difficulty (the number of skulls killed so far).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum DifficultyCurve {
+    Constant(f32),
+    /// `base + slope * difficulty`
+    Linear {
+        base: f32,
+        slope: f32,
+    },
+    /// `base + scale * difficulty^exponent`
+    Power {
+        base: f32,
+        scale: f32,
+        exponent: f32,
+    },
+    /// Goes from `start` towards `end`, reaching halfway at a difficulty of `half_at`.
+    Saturating {
+        start: f32,
+        end: f32,
+        half_at: f32,
+    },
+    /// Straight lines between `(difficulty, value)` points, flat past either end.
+    Points(Vec<(f32, f32)>),
+}
+
+impl DifficultyCurve {
+    pub fn sample(&self, difficulty: f32) -> f32 {
+        match self {
+            DifficultyCurve::Constant(value) => *value,
+            DifficultyCurve::Linear { base, slope } => base + slope * difficulty,
+            DifficultyCurve::Power {
+                base,
+                scale,
+                exponent,
+            } => base + scale * difficulty.powf(*exponent),
+            DifficultyCurve::Saturating {
+                start,
+                end,
+                half_at,
+            } => start + (end - start) * difficulty / (difficulty + half_at),
+            DifficultyCurve::Points(points) => {
+                let Some(&(first_x, first_y)) = points.first() else {
+                    return 0.0;
+                };
+                if difficulty <= first_x {
+                    return first_y;
+                }
+                for pair in points.windows(2) {
+                    let ((x0, y0), (x1, y1)) = (pair[0], pair[1]);
+                    if difficulty <= x1 {
+                        let t = (difficulty - x0) / (x1 - x0).max(f32::EPSILON);
+                        return y0.lerp(y1, t);
+                    }
+                }
+                points.last().map_or(first_y, |&(_, y)| y)
+            }
+        }
+    }
+}
+
+/// Tuning for how enemies are spawned. Loaded from `assets/data/spawn.director.ron` by
+/// [`SpawnDirectorAssetPlugin`].
+#[derive(Debug, Clone, Asset, TypePath, Resource, Serialize, Deserialize)]
+pub struct SpawnDirector {
+    /// Skulls each new spawner releases in endless mode.
+    pub skulls_per_spawner: DifficultyCurve,
+    /// Seconds between new spawners in endless mode.
+    pub spawner_delay: DifficultyCurve,
+    /// How likely each kind of enemy is to come out of a spawner in endless mode.
+    #[serde(default)]
+    pub enemy_weights: Vec<(EnemyKind, DifficultyCurve)>,
+    /// Spawners are placed within this distance of the centre of the arena.
+    pub spawn_radius: f32,
+    /// ...and at least this far from the player.
+    pub min_player_distance: f32,
+    /// Seconds between skulls coming out of a spawner.
+    pub spawner_fire_interval: f32,
+    /// Spawners hold off while there are this many skulls alive.
+    pub max_concurrent_skulls: usize,
+}
+
+impl Default for SpawnDirector {
+    /// The director in `assets/data/spawn.director.ron`, built in so there's one without the asset
+    /// plugin too.
+    fn default() -> Self {
+        ron::de::from_str(include_str!("../assets/data/spawn.director.ron"))
+            .expect("the built-in spawn director should parse")
+    }
+}
+
+impl SpawnDirector {
+    pub fn enemy_mix(&self, difficulty: f32) -> EnemyMix {
+        if self.enemy_weights.is_empty() {
+            return EnemyMix::default();
+        }
+        EnemyMix(
+            self.enemy_weights
+                .iter()
+                .map(|(kind, weight)| (*kind, weight.sample(difficulty).max(0.0)))
+                .collect(),
+        )
+    }
+
+    /// Picks a random spot in the arena that isn't right on top of the player.
+    pub fn random_spawn_pos(&self, index: &SpatialIndex, rng: &mut Entropy<WyRand>) -> Vec3 {
+        let spawn_area = Circle::new(self.spawn_radius);
+        // give up eventually if the radius is too small to ever be far enough away
+        for _ in 0..100 {
+            let pos = spawn_area.sample_interior(&mut *rng);
+            let pos = Vec3::new(pos.x, 0.0, pos.y);
+            if !index.any_within(pos, self.min_player_distance, PLAYER_GROUP) {
+                return pos;
+            }
+        }
+        warn!("couldn't find a spawn position far enough from the player");
+        let pos = spawn_area.sample_interior(rng);
+        Vec3::new(pos.x, 0.0, pos.y)
+    }
+}
+
 #[derive(Debug, Resource)]
 struct SpawnParameters {
     skulls_to_spawn: f32,
@@ -56,41 +234,74 @@ struct SpawnParameters {
     difficulty: f32,
 }
 
-impl Default for SpawnParameters {
-    fn default() -> Self {
-        SpawnParameters {
-            skulls_to_spawn: 5.0,
-            delay_before_next_spawner: 10.0,
-            timer: Timer::from_seconds(10.0, TimerMode::Once),
+impl SpawnParameters {
+    fn new(director: &SpawnDirector) -> Self {
+        let mut parameters = SpawnParameters {
+            skulls_to_spawn: 0.0,
+            delay_before_next_spawner: 0.0,
+            timer: Timer::default(),
             difficulty: 0.0,
-        }
+        };
+        parameters.set_difficulty(0.0, director);
+        parameters.timer =
+            Timer::from_seconds(parameters.delay_before_next_spawner, TimerMode::Once);
+        parameters
     }
-}
 
-impl SpawnParameters {
-    fn set_difficulty(&mut self, difficulty: f32) {
+    fn set_difficulty(&mut self, difficulty: f32, director: &SpawnDirector) {
         self.difficulty = difficulty;
 
-        self.skulls_to_spawn = 5.0 + 0.4 * f32::sqrt(30.0 * difficulty);
-        self.delay_before_next_spawner = 10.0 - (5.0 * difficulty / (difficulty + 100.0));
+        self.skulls_to_spawn = director.skulls_per_spawner.sample(difficulty);
+        self.delay_before_next_spawner = director.spawner_delay.sample(difficulty);
     }
 }
 
-fn reset_spawn_parameters(mut commands: Commands) {
-    commands.insert_resource(SpawnParameters::default());
+fn reset_spawn_parameters(mut commands: Commands, director: Res<SpawnDirector>) {
+    commands.insert_resource(SpawnParameters::new(&director));
+}
+
+/// Weighted list of the enemies a spawner picks from.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EnemyMix(pub Vec<(EnemyKind, f32)>);
+
+impl Default for EnemyMix {
+    fn default() -> Self {
+        EnemyMix(vec![(EnemyKind::FireSkull, 1.0)])
+    }
+}
+
+impl EnemyMix {
+    fn pick(&self, rng: &mut impl RngCore) -> EnemyKind {
+        // don't draw from the rng when there's nothing to choose between
+        if let [(kind, _)] = self.0.as_slice() {
+            return *kind;
+        }
+        let total: f32 = self.0.iter().map(|(_, weight)| weight).sum();
+        let mut roll = rng.next_u32() as f32 / u32::MAX as f32 * total;
+        for (kind, weight) in self.0.iter() {
+            if roll < *weight {
+                return *kind;
+            }
+            roll -= weight;
+        }
+        self.0.last().map(|(kind, _)| *kind).unwrap_or_default()
+    }
 }
 
 #[derive(Debug, Component)]
-struct Spawner {
+#[require(Transform, Visibility)]
+pub struct Spawner {
     skulls_left: usize,
+    mix: EnemyMix,
     timer: Timer,
 }
 
 impl Spawner {
-    pub fn new(skulls_left: usize) -> Self {
+    pub fn new(skulls_left: usize, mix: EnemyMix, fire_interval: f32) -> Self {
         Spawner {
             skulls_left,
-            timer: Timer::from_seconds(0.75, TimerMode::Repeating),
+            mix,
+            timer: Timer::from_seconds(fire_interval, TimerMode::Repeating),
         }
     }
 }
@@ -99,9 +310,10 @@ fn create_spawners(
     time: Res<Time>,
     mut writer: EventWriter<CreateSpawnerEvent>,
     mut spawn_parameters: ResMut<SpawnParameters>,
-    player_query: Single<&GlobalTransform, With<Player>>,
+    index: Res<SpatialIndex>,
     kill_count: Res<SkullsKilled>,
-    mut rng: GlobalEntropy<WyRand>,
+    director: Res<SpawnDirector>,
+    mut rng: ResMut<StreamRng<SpawnerRng>>,
 ) {
     spawn_parameters.timer.tick(time.delta());
     if !spawn_parameters.timer.just_finished() {
@@ -109,37 +321,31 @@ fn create_spawners(
     }
 
     let new_difficulty = kill_count.count as f32;
-    spawn_parameters.set_difficulty(new_difficulty);
+    spawn_parameters.set_difficulty(new_difficulty, &director);
     let new_delay = spawn_parameters.delay_before_next_spawner;
     spawn_parameters
         .timer
         .set_duration(Duration::from_secs_f32(new_delay));
     spawn_parameters.timer.reset();
 
-    let player_pos = player_query.translation();
-    let spawn_area = Circle::new(50.0);
-    let spawn_pos = loop {
-        let pos = spawn_area.sample_interior(rng.as_mut());
-        let pos = Vec3::new(pos.x, 0.0, pos.y);
-        const FAR_ENOUGH: f32 = 15.0;
-        if player_pos.distance(pos) >= FAR_ENOUGH {
-            break pos;
-        }
-    };
+    let spawn_pos = director.random_spawn_pos(&index, &mut rng);
 
     writer.write(CreateSpawnerEvent {
         pos: spawn_pos,
         skulls_left: spawn_parameters.skulls_to_spawn as usize,
+        mix: director.enemy_mix(new_difficulty),
     });
 }
 
 fn create_first_spawner(
     mut writer: EventWriter<CreateSpawnerEvent>,
     spawn_parameters: Res<SpawnParameters>,
+    director: Res<SpawnDirector>,
 ) {
     writer.write(CreateSpawnerEvent {
         pos: Vec3::new(0.0, 0.0, -20.0),
         skulls_left: spawn_parameters.skulls_to_spawn as usize,
+        mix: director.enemy_mix(0.0),
     });
 }
 
@@ -147,11 +353,19 @@ fn run_spawners(
     time: Res<Time>,
     mut commands: Commands,
     mut query: Query<(Entity, &mut Spawner, &GlobalTransform)>,
+    skull_query: Query<(), With<FireSkull>>,
+    director: Res<SpawnDirector>,
+    mut rng: ResMut<StreamRng<SpawnerRng>>,
 ) {
+    let mut alive = skull_query.iter().count();
     for (entity, mut spawner, global_transform) in query.iter_mut() {
         spawner.timer.tick(time.delta());
 
         if spawner.timer.just_finished() {
+            if alive >= director.max_concurrent_skulls {
+                continue;
+            }
+            alive += 1;
             let pos = global_transform.translation();
 
             spawner.skulls_left -= 1;
@@ -162,11 +376,7 @@ fn run_spawners(
                 }
             }
 
-            commands.spawn((
-                FireSkull::default(),
-                Transform::from_translation(pos),
-                StateScoped(GameState::InGame),
-            ));
+            spawner.mix.pick(&mut **rng).spawn(&mut commands, pos);
         }
     }
 }
@@ -175,6 +385,7 @@ fn run_spawners(
 pub struct CreateSpawnerEvent {
     pub pos: Vec3,
     pub skulls_left: usize,
+    pub mix: EnemyMix,
 }
 
 #[derive(Debug, Resource, AssetCollection)]
@@ -188,10 +399,32 @@ struct SpawnerAssets {
 fn spawn_spawners(
     mut commands: Commands,
     mut reader: EventReader<CreateSpawnerEvent>,
+    director: Res<SpawnDirector>,
+) {
+    for CreateSpawnerEvent {
+        pos,
+        skulls_left,
+        mix,
+    } in reader.read()
+    {
+        if *skulls_left == 0 {
+            continue;
+        }
+        commands.spawn((
+            Spawner::new(*skulls_left, mix.clone(), director.spawner_fire_interval),
+            Transform::from_translation(*pos),
+            StateScoped(GameState::InGame),
+        ));
+    }
+}
+
+fn spawn_spawner_visuals(
+    mut commands: Commands,
+    query: Query<Entity, Added<Spawner>>,
     assets: Res<SpawnerAssets>,
     mut sprite3d_params: Sprite3dParams,
 ) {
-    for CreateSpawnerEvent { pos, skulls_left } in reader.read() {
+    for entity in query.iter() {
         let atlas = TextureAtlas {
             layout: assets.explosion_atlas_layout.clone(),
             index: 0,
@@ -212,13 +445,19 @@ fn spawn_spawners(
             ..Default::default()
         }
         .bundle_with_atlas(&mut sprite3d_params, atlas);
-        commands.spawn((
-            Spawner::new(*skulls_left),
-            explosion,
-            animation,
-            FaceCamera::default(),
-            Transform::from_translation(*pos).with_scale(Vec3::splat(8.0)),
-            StateScoped(GameState::InGame),
-        ));
+        let visual = commands
+            .spawn((
+                explosion,
+                animation,
+                FaceCamera::default(),
+                Transform::from_scale(Vec3::splat(8.0)),
+            ))
+            .id();
+
+        if let Ok(mut c) = commands.get_entity(entity) {
+            c.add_child(visual);
+        } else {
+            commands.entity(visual).despawn();
+        }
     }
 }
@@ -45,4 +45,7 @@ pub enum PauseState {
     #[default]
     Unpaused,
     Paused,
+    /// Browsing the upgrade shop between waves. Gameplay is frozen, but unlike when paused the
+    /// fixed timestep keeps ticking, so purchases happen on a tick like any other input.
+    Shopping,
 }
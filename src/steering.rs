@@ -0,0 +1,114 @@
+//! Keeps groups of enemies from collapsing into a single column while they chase the player.
+//!
+//! Each enemy's own movement (see [`SkullMovement`](crate::fire_skull::SkullMovement)) decides
+//! where it wants to go; the forces computed here are added on top of that.
+
+use bevy::prelude::*;
+
+use crate::{
+    level::ARENA_HALF_SIZE,
+    physics::ENEMY_GROUP,
+    spatial::SpatialIndex,
+    states::{GameState, PauseState},
+};
+
+#[derive(Debug, Default)]
+pub struct SteeringPlugin;
+
+impl Plugin for SteeringPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            compute_steering
+                .in_set(SteeringSet)
+                .run_if(in_state(GameState::InGame).and(in_state(PauseState::Unpaused))),
+        );
+    }
+}
+
+/// Computes [`SteeringForce`]s. Anything turning them into movement should run after this.
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SteeringSet;
+
+/// How strongly an enemy reacts to the ones around it. Weights are relative to the enemy's own
+/// movement, which has a weight of `seek`.
+#[derive(Debug, Clone, Copy, PartialEq, Component)]
+#[require(SteeringForce)]
+pub struct Steering {
+    pub seek: f32,
+    /// Pushes away from neighbours closer than `separation_radius`.
+    pub separation: f32,
+    pub separation_radius: f32,
+    /// Pulls towards the centre of neighbours closer than `cohesion_radius`.
+    pub cohesion: f32,
+    pub cohesion_radius: f32,
+    /// Turns away from the edge of the arena when closer than `look_ahead`.
+    pub avoidance: f32,
+    pub look_ahead: f32,
+}
+
+impl Default for Steering {
+    fn default() -> Self {
+        Steering {
+            seek: 1.0,
+            separation: 1.5,
+            separation_radius: 1.5,
+            cohesion: 0.3,
+            cohesion_radius: 6.0,
+            avoidance: 2.0,
+            look_ahead: 5.0,
+        }
+    }
+}
+
+/// The combined separation, cohesion and avoidance for this frame.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Component)]
+pub struct SteeringForce(pub Vec3);
+
+fn compute_steering(
+    index: Res<SpatialIndex>,
+    mut query: Query<(Entity, &GlobalTransform, &Steering, &mut SteeringForce)>,
+) {
+    for (entity, transform, steering, mut force) in query.iter_mut() {
+        let pos = transform.translation().with_y(0.0);
+        let radius = steering.separation_radius.max(steering.cohesion_radius);
+
+        let mut separation = Vec3::ZERO;
+        let mut centre = Vec3::ZERO;
+        let mut neighbours = 0;
+        for other in index.within(pos, radius, ENEMY_GROUP) {
+            if other.entity == entity {
+                continue;
+            }
+            let other_pos = other.pos.with_y(0.0);
+            let offset = pos - other_pos;
+            let dist = offset.length();
+            if dist < steering.separation_radius && dist > f32::EPSILON {
+                // stronger the closer they are
+                separation += offset / (dist * dist);
+            }
+            if dist < steering.cohesion_radius {
+                centre += other_pos;
+                neighbours += 1;
+            }
+        }
+        let cohesion = if neighbours > 0 {
+            (centre / neighbours as f32 - pos).normalize_or_zero()
+        } else {
+            Vec3::ZERO
+        };
+
+        let mut avoidance = Vec3::ZERO;
+        let edge = ARENA_HALF_SIZE - steering.look_ahead;
+        if pos.x.abs() > edge {
+            avoidance.x = -pos.x.signum() * (pos.x.abs() - edge) / steering.look_ahead;
+        }
+        if pos.z.abs() > edge {
+            avoidance.z = -pos.z.signum() * (pos.z.abs() - edge) / steering.look_ahead;
+        }
+
+        force.0 = separation.clamp_length_max(1.0) * steering.separation
+            + cohesion * steering.cohesion
+            + avoidance.clamp_length_max(1.0) * steering.avoidance;
+    }
+}
@@ -0,0 +1,115 @@
+//! Tiny key-value store for things that should outlive a session, like replays and high scores.
+//!
+//! On native, each key is a file in the `saves` directory. On the web, keys live in
+//! `localStorage`, with values hex-encoded since it can only hold strings.
+
+use std::io;
+
+#[cfg(not(target_family = "wasm"))]
+mod backend {
+    use std::{fs, io, path::PathBuf};
+
+    fn dir() -> PathBuf {
+        PathBuf::from("saves")
+    }
+
+    pub fn save(key: &str, data: &[u8]) -> io::Result<()> {
+        fs::create_dir_all(dir())?;
+        fs::write(dir().join(key), data)
+    }
+
+    pub fn load(key: &str) -> io::Result<Option<Vec<u8>>> {
+        match fs::read(dir().join(key)) {
+            Ok(data) => Ok(Some(data)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn keys() -> io::Result<Vec<String>> {
+        let entries = match fs::read_dir(dir()) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        let mut keys = Vec::new();
+        for entry in entries {
+            if let Some(name) = entry?.file_name().to_str() {
+                keys.push(name.to_string());
+            }
+        }
+        Ok(keys)
+    }
+}
+
+#[cfg(target_family = "wasm")]
+mod backend {
+    use std::io;
+
+    use web_sys::Storage;
+
+    const PREFIX: &str = "exploding-skulls/";
+
+    fn storage() -> io::Result<Storage> {
+        web_sys::window()
+            .and_then(|window| window.local_storage().ok().flatten())
+            .ok_or_else(|| io::Error::other("localStorage is not available"))
+    }
+
+    fn js_error(e: web_sys::wasm_bindgen::JsValue) -> io::Error {
+        io::Error::other(format!("{e:?}"))
+    }
+
+    pub fn save(key: &str, data: &[u8]) -> io::Result<()> {
+        let encoded: String = data.iter().map(|b| format!("{b:02x}")).collect();
+        storage()?
+            .set_item(&format!("{PREFIX}{key}"), &encoded)
+            .map_err(js_error)
+    }
+
+    pub fn load(key: &str) -> io::Result<Option<Vec<u8>>> {
+        let Some(encoded) = storage()?
+            .get_item(&format!("{PREFIX}{key}"))
+            .map_err(js_error)?
+        else {
+            return Ok(None);
+        };
+        (0..encoded.len())
+            .step_by(2)
+            .map(|i| {
+                encoded
+                    .get(i..i + 2)
+                    .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+                    .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "bad hex"))
+            })
+            .collect::<io::Result<Vec<u8>>>()
+            .map(Some)
+    }
+
+    pub fn keys() -> io::Result<Vec<String>> {
+        let storage = storage()?;
+        let mut keys = Vec::new();
+        for i in 0..storage.length().map_err(js_error)? {
+            if let Some(key) = storage.key(i).map_err(js_error)? {
+                if let Some(key) = key.strip_prefix(PREFIX) {
+                    keys.push(key.to_string());
+                }
+            }
+        }
+        Ok(keys)
+    }
+}
+
+pub fn save(key: &str, data: &[u8]) -> io::Result<()> {
+    backend::save(key, data)
+}
+
+/// Returns `Ok(None)` if nothing has been saved under `key`.
+pub fn load(key: &str) -> io::Result<Option<Vec<u8>>> {
+    backend::load(key)
+}
+
+/// All saved keys, in no particular order.
+pub fn keys() -> io::Result<Vec<String>> {
+    backend::keys()
+}
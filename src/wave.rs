@@ -0,0 +1,229 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_asset_loader::asset_collection::AssetCollection;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    assets::{AssetLoadingExt, SingleAssetCollection, apply_ron_asset},
+    fire_skull::FireSkull,
+    game_mode::{GameMode, in_game_mode},
+    rand::{RandSet, RngStream, RngStreamAppExt, StreamRng},
+    spatial::SpatialIndex,
+    spawner::{CreateSpawnerEvent, EnemyMix, SpawnDirector, Spawner},
+    states::{GameState, PauseState},
+};
+
+#[derive(Debug, Default)]
+pub struct WavePlugin;
+
+impl Plugin for WavePlugin {
+    fn build(&self, app: &mut App) {
+        app.register_ron_asset::<WaveTable>(&["waves.ron"])
+            .init_resource::<WaveTable>()
+            .init_resource::<WaveState>()
+            .add_rng_stream::<WaveRng>()
+            .add_state_scoped_event::<WaveCleared>(GameState::InGame)
+            .add_systems(
+                OnEnter(GameState::InGame),
+                reset_waves.after(RandSet::ReseedStreams),
+            )
+            .add_systems(
+                FixedUpdate,
+                run_waves.run_if(
+                    in_state(GameState::InGame)
+                        .and(in_state(PauseState::Unpaused))
+                        .and(in_game_mode(GameMode::Waves)),
+                ),
+            );
+    }
+}
+
+/// Reloads the [`WaveTable`] whenever `assets/data/default.waves.ron` changes. Without this the
+/// copy built into the game is used.
+#[derive(Debug, Default)]
+pub struct WaveTableAssetPlugin;
+
+impl Plugin for WaveTableAssetPlugin {
+    fn build(&self, app: &mut App) {
+        app.load_asset_on_startup::<WaveTableAssets>()
+            .add_systems(Update, apply_ron_asset::<WaveTable, WaveTableAssets>);
+    }
+}
+
+#[derive(Resource, AssetCollection)]
+struct WaveTableAssets {
+    #[asset(path = "data/default.waves.ron")]
+    table: Handle<WaveTable>,
+}
+
+impl SingleAssetCollection<WaveTable> for WaveTableAssets {
+    fn handle(&self) -> &Handle<WaveTable> {
+        &self.table
+    }
+}
+
+struct WaveRng;
+
+impl RngStream for WaveRng {
+    const NAME: &'static str = "wave";
+}
+
+/// One wave of [`GameMode::Waves`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WaveDef {
+    pub spawners: u32,
+    pub skulls_per_spawner: u32,
+    #[serde(default)]
+    pub enemy_mix: EnemyMix,
+    /// Seconds before the next wave starts whether or not this one has been cleared.
+    #[serde(default)]
+    pub time_limit: Option<f32>,
+}
+
+/// The waves played in [`GameMode::Waves`], in order. Loaded from
+/// `assets/data/default.waves.ron` by [`WaveTableAssetPlugin`].
+#[derive(Debug, Clone, Asset, TypePath, Resource, Serialize, Deserialize)]
+pub struct WaveTable {
+    pub waves: Vec<WaveDef>,
+    /// Seconds between clearing a wave and the next one starting.
+    pub intermission: f32,
+}
+
+impl Default for WaveTable {
+    /// The table in `assets/data/default.waves.ron`, built in so there's one without the asset
+    /// plugin too.
+    fn default() -> Self {
+        ron::de::from_str(include_str!("../assets/data/default.waves.ron"))
+            .expect("the built-in wave table should parse")
+    }
+}
+
+impl WaveTable {
+    /// Returns wave `number`, counting from 1. Past the end of the table the last wave is repeated
+    /// with more skulls every time.
+    pub fn wave(&self, number: u32) -> Option<WaveDef> {
+        let last = self.waves.len() as u32;
+        if let Some(wave) = self.waves.get(number.checked_sub(1)? as usize) {
+            return Some(wave.clone());
+        }
+        let mut wave = self.waves.last()?.clone();
+        let extra = number - last;
+        wave.spawners += extra / 2;
+        wave.skulls_per_spawner += extra;
+        Some(wave)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum WavePhase {
+    /// Waiting for the next wave to start.
+    Intermission(Timer),
+    Active {
+        started: Duration,
+        time_limit: Option<Timer>,
+    },
+}
+
+#[derive(Debug, Clone, Resource)]
+pub struct WaveState {
+    /// The current wave, counting from 1. 0 before the first wave starts.
+    pub number: u32,
+    pub phase: WavePhase,
+}
+
+impl WaveState {
+    /// Delay before the first wave.
+    const FIRST_WAVE_DELAY: f32 = 3.0;
+}
+
+impl Default for WaveState {
+    fn default() -> Self {
+        WaveState {
+            number: 0,
+            phase: WavePhase::Intermission(Timer::from_seconds(
+                Self::FIRST_WAVE_DELAY,
+                TimerMode::Once,
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Event)]
+pub struct WaveCleared {
+    pub wave: u32,
+    /// How long the wave took to clear.
+    pub duration: Duration,
+}
+
+fn reset_waves(mut commands: Commands) {
+    commands.insert_resource(WaveState::default());
+}
+
+fn run_waves(
+    time: Res<Time>,
+    table: Res<WaveTable>,
+    mut state: ResMut<WaveState>,
+    mut writer: EventWriter<CreateSpawnerEvent>,
+    mut cleared_writer: EventWriter<WaveCleared>,
+    pending_spawners: Res<Events<CreateSpawnerEvent>>,
+    spawner_query: Query<(), With<Spawner>>,
+    skull_query: Query<(), With<FireSkull>>,
+    index: Res<SpatialIndex>,
+    director: Res<SpawnDirector>,
+    mut rng: ResMut<StreamRng<WaveRng>>,
+) {
+    let state = &mut *state;
+    let now = time.elapsed();
+    let start_next = match state.phase {
+        WavePhase::Intermission(ref mut timer) => timer.tick(time.delta()).just_finished(),
+        WavePhase::Active {
+            started,
+            ref mut time_limit,
+        } => {
+            // spawners requested in the last couple of frames might not exist yet
+            let cleared =
+                pending_spawners.is_empty() && spawner_query.is_empty() && skull_query.is_empty();
+            if cleared {
+                info!("wave {} cleared", state.number);
+                cleared_writer.write(WaveCleared {
+                    wave: state.number,
+                    duration: now.saturating_sub(started),
+                });
+                state.phase = WavePhase::Intermission(Timer::from_seconds(
+                    table.intermission,
+                    TimerMode::Once,
+                ));
+                false
+            } else {
+                time_limit
+                    .as_mut()
+                    .is_some_and(|timer| timer.tick(time.delta()).just_finished())
+            }
+        }
+    };
+    if !start_next {
+        return;
+    }
+
+    let Some(wave) = table.wave(state.number + 1) else {
+        warn!("wave table is empty");
+        return;
+    };
+    state.number += 1;
+    info!("starting wave {}", state.number);
+
+    for _ in 0..wave.spawners {
+        writer.write(CreateSpawnerEvent {
+            pos: director.random_spawn_pos(&index, &mut rng),
+            skulls_left: wave.skulls_per_spawner as usize,
+            mix: wave.enemy_mix.clone(),
+        });
+    }
+    state.phase = WavePhase::Active {
+        started: now,
+        time_limit: wave
+            .time_limit
+            .map(|secs| Timer::from_seconds(secs, TimerMode::Once)),
+    };
+}
@@ -0,0 +1,1216 @@
+use std::time::Duration;
+
+use bevy::{prelude::*, render::view::RenderLayers};
+use bevy_asset_loader::asset_collection::AssetCollection;
+use bevy_rand::prelude::{Entropy, WyRand};
+use bevy_rapier3d::prelude::*;
+use bevy_sprite3d::prelude::*;
+use leafwing_input_manager::prelude::ActionState;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    assets::{AssetLoadingExt, SingleAssetCollection, apply_ron_asset},
+    chain::{ChainRoot, ChainTracker},
+    character_controller::ReadHeading,
+    explosion::{ExplosionEvent, Falloff, Occlusion},
+    health::{DamageEvent, DamageType, Health},
+    input::{InputAction, InputState},
+    physics::{ENEMY_GROUP, PROJECTILE_GROUP, WALL_GROUP, WEAPON_GROUP},
+    pickup::{DoubleDamage, RapidReload},
+    player::Player,
+    rand::{RngStream, RngStreamAppExt, StreamRng},
+    score::RunStats,
+    shop::Upgrades,
+    sprite::{AnimatedSprite3d, FaceCamera},
+    states::{GameState, PauseState},
+};
+
+#[derive(Debug, Default)]
+pub struct WeaponPlugin;
+
+impl Plugin for WeaponPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_ron_asset::<WeaponLoadout>(&["weapons.ron"])
+            .init_resource::<WeaponLoadout>()
+            .add_rng_stream::<WeaponRng>()
+            .add_state_scoped_event::<WeaponEvent>(GameState::InGame)
+            .add_state_scoped_event::<PelletImpact>(GameState::InGame)
+            .add_systems(
+                FixedUpdate,
+                (
+                    switch_weapons,
+                    update_weapons,
+                    fire_weapons,
+                    move_weapon_projectiles,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::InGame).and(in_state(PauseState::Unpaused))),
+            );
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct WeaponVisualsPlugin;
+
+impl Plugin for WeaponVisualsPlugin {
+    fn build(&self, app: &mut App) {
+        app.load_asset_on_startup::<WeaponAssets>()
+            .add_systems(OnEnter(GameState::InGame), setup_view_model)
+            .add_systems(
+                Update,
+                (
+                    spawn_view_models,
+                    build_view_models,
+                    animate_view_model,
+                    spawn_weapon_projectile_visuals,
+                    spawn_pellet_impacts,
+                )
+                    .run_if(in_state(GameState::InGame).and(in_state(PauseState::Unpaused))),
+            );
+    }
+}
+
+/// Loads the [`WeaponLoadout`] from `assets/data`, and reloads it whenever the file changes.
+/// Without this the built-in weapons are used.
+#[derive(Debug, Default)]
+pub struct WeaponLoadoutAssetPlugin;
+
+impl Plugin for WeaponLoadoutAssetPlugin {
+    fn build(&self, app: &mut App) {
+        app.load_asset_on_startup::<WeaponLoadoutAssets>()
+            .add_systems(
+                Update,
+                apply_ron_asset::<WeaponLoadout, WeaponLoadoutAssets>,
+            );
+    }
+}
+
+#[derive(Resource, AssetCollection)]
+struct WeaponLoadoutAssets {
+    #[asset(path = "data/player.weapons.ron")]
+    loadout: Handle<WeaponLoadout>,
+}
+
+impl SingleAssetCollection<WeaponLoadout> for WeaponLoadoutAssets {
+    fn handle(&self) -> &Handle<WeaponLoadout> {
+        &self.loadout
+    }
+}
+
+struct WeaponRng;
+
+impl RngStream for WeaponRng {
+    const NAME: &'static str = "weapon";
+}
+
+#[derive(Debug, Clone, Copy, Event, Hash, PartialEq, Eq)]
+pub enum WeaponEvent {
+    /// `entity` pulled the trigger and a round went off.
+    Fire { entity: Entity, slot: WeaponSlot },
+    /// `entity` started reloading.
+    Reload { entity: Entity, slot: WeaponSlot },
+}
+
+/// Which of an entity's weapons an event is about.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum WeaponSlot {
+    /// The [`Weapon`].
+    Primary,
+    /// The [`AltWeapon`].
+    Alt,
+}
+
+/// Everything that makes one weapon different from another.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WeaponDef {
+    pub name: String,
+    /// Rounds fired before having to reload.
+    pub magazine: u32,
+    pub fire_mode: FireMode,
+    /// Seconds the weapon is busy after each round, before it can fire or reload again.
+    pub fire_time: f32,
+    pub reload: ReloadStyle,
+    /// Pellets fired by each round.
+    pub pellets: u32,
+    /// Half angle of the cone the pellets are spread over, in degrees.
+    pub spread: f32,
+    pub delivery: Delivery,
+    /// Damage dealt by each pellet, before falloff.
+    pub damage: f32,
+    pub damage_type: DamageType,
+    pub falloff: RangeFalloff,
+    /// What it looks like in the player's hands.
+    pub view_model: ViewModelAtlas,
+    pub frames: ViewModelFrames,
+}
+
+impl WeaponDef {
+    /// Directions of every pellet in a round fired towards `forward`, scattered uniformly over
+    /// the spread cone.
+    pub fn pellet_directions(&self, forward: Vec3, rng: &mut Entropy<WyRand>) -> Vec<Vec3> {
+        let cone = Circle::new(self.spread.to_radians().tan());
+        let right = forward.cross(Vec3::Y).normalize_or(Vec3::X);
+        let up = right.cross(forward);
+        (0..self.pellets.max(1))
+            .map(|_| {
+                if self.spread <= 0.0 {
+                    return forward;
+                }
+                let offset = cone.sample_interior(&mut *rng);
+                (forward + right * offset.x + up * offset.y).normalize()
+            })
+            .collect()
+    }
+}
+
+/// The weapons the player starts a run with. Loaded from `assets/data/player.weapons.ron` by
+/// [`WeaponLoadoutAssetPlugin`]; changes are picked up from the next run.
+#[derive(Debug, Clone, Asset, TypePath, Resource, Serialize, Deserialize)]
+pub struct WeaponLoadout {
+    /// The [`Weapon`] in hand at the start.
+    pub primary: WeaponDef,
+    /// The rest of the [`WeaponInventory`], in slot order.
+    pub holstered: Vec<WeaponDef>,
+    /// The [`AltWeapon`].
+    pub alt: WeaponDef,
+}
+
+impl Default for WeaponLoadout {
+    /// The loadout in `assets/data/player.weapons.ron`, built in so there's one without the asset
+    /// plugin too.
+    fn default() -> Self {
+        ron::de::from_str(include_str!("../assets/data/player.weapons.ron"))
+            .expect("the built-in weapons should parse")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum FireMode {
+    /// Once per pull of the trigger.
+    Semi,
+    /// For as long as the trigger is held.
+    Auto,
+    /// `count` rounds per pull of the trigger, as fast as the weapon can fire them.
+    Burst { count: u32 },
+    /// Once when the trigger is pulled, and again when it's let go.
+    PressAndRelease,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ReloadStyle {
+    /// Refills the whole magazine after `time` seconds.
+    Full { time: f32 },
+    /// Loads one round every `time` seconds. Pulling the trigger stops reloading once the current
+    /// round is in.
+    PerShell { time: f32 },
+    /// Never reloads, but gets a round back every `time` seconds while it isn't full.
+    Recharge { time: f32 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Delivery {
+    /// Hits the first thing a ball of `radius` swept along the pellet's path touches.
+    Hitscan { radius: f32 },
+    /// Fires a [`WeaponProjectile`] for each pellet.
+    Projectile {
+        speed: f32,
+        radius: f32,
+        /// Seconds before it's despawned.
+        lifetime: f32,
+        /// Blows up on impact if set.
+        explosion: Option<ImpactExplosion>,
+    },
+}
+
+/// An [`ExplosionEvent`] started by a [`WeaponProjectile`] hitting something.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ImpactExplosion {
+    pub scale: f32,
+    pub damage: f32,
+    pub falloff: Falloff,
+    pub damage_type: DamageType,
+}
+
+/// Full damage up to `start` metres, dropping linearly to nothing at `end`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RangeFalloff {
+    pub start: f32,
+    pub end: f32,
+}
+
+impl RangeFalloff {
+    pub fn sample(&self, dist: f32) -> f32 {
+        if dist <= self.start {
+            1.0
+        } else {
+            let t = (dist - self.start) / (self.end - self.start);
+            f32::max(1.0 - t, 0.0)
+        }
+    }
+}
+
+/// Indices into the view model's texture atlas.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ViewModelFrames {
+    pub idle: usize,
+    pub firing: usize,
+    pub reloading: usize,
+}
+
+/// A grid of view model frames in a single image.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ViewModelAtlas {
+    /// Path of the image, relative to `assets`.
+    pub image: String,
+    /// Width and height of each frame, in pixels.
+    pub tile_size: (u32, u32),
+    pub columns: u32,
+    pub rows: u32,
+}
+
+impl ViewModelAtlas {
+    pub fn layout(&self) -> TextureAtlasLayout {
+        let (width, height) = self.tile_size;
+        TextureAtlasLayout::from_grid(
+            UVec2::new(width, height),
+            self.columns,
+            self.rows,
+            None,
+            None,
+        )
+    }
+}
+
+#[derive(Debug, Component)]
+pub struct Weapon {
+    pub def: WeaponDef,
+    pub state: WeaponState,
+    pub next_state: WeaponState,
+    pub rounds: u32,
+    /// Whether the trigger is still held from the last round fired.
+    awaiting_release: bool,
+    burst_left: u32,
+    /// Only used by [`ReloadStyle::Recharge`].
+    recharge_timer: Timer,
+}
+
+impl Weapon {
+    pub fn new(def: WeaponDef) -> Self {
+        let recharge_time = match def.reload {
+            ReloadStyle::Recharge { time } => time,
+            _ => 0.0,
+        };
+        Weapon {
+            rounds: def.magazine,
+            def,
+            state: WeaponState::Idle,
+            next_state: WeaponState::Idle,
+            awaiting_release: false,
+            burst_left: 0,
+            recharge_timer: Timer::from_seconds(recharge_time, TimerMode::Repeating),
+        }
+    }
+
+    fn should_fire(&self, trigger: bool) -> bool {
+        if self.rounds == 0 {
+            return false;
+        }
+        match self.def.fire_mode {
+            FireMode::Semi => trigger && !self.awaiting_release,
+            FireMode::Auto => trigger,
+            FireMode::Burst { .. } => self.burst_left > 0 || (trigger && !self.awaiting_release),
+            FireMode::PressAndRelease => trigger != self.awaiting_release,
+        }
+    }
+
+    fn fire(&mut self, trigger: bool) {
+        match self.def.fire_mode {
+            FireMode::Semi | FireMode::Auto => self.awaiting_release = trigger,
+            FireMode::Burst { count } => {
+                if self.burst_left == 0 {
+                    self.burst_left = count.max(1);
+                    self.awaiting_release = true;
+                }
+                self.burst_left -= 1;
+            }
+            FireMode::PressAndRelease => self.awaiting_release = !self.awaiting_release,
+        }
+
+        self.rounds -= 1;
+        self.state = WeaponState::Firing {
+            firing_timer: Timer::from_seconds(self.def.fire_time, TimerMode::Once),
+        };
+        self.next_state = if self.rounds == 0 {
+            self.burst_left = 0;
+            self.reload_state()
+        } else {
+            WeaponState::Idle
+        };
+    }
+
+    fn reload_state(&self) -> WeaponState {
+        let time = match self.def.reload {
+            ReloadStyle::Full { time } | ReloadStyle::PerShell { time } => time,
+            // rounds come back on their own
+            ReloadStyle::Recharge { .. } => return WeaponState::Idle,
+        };
+        WeaponState::Reloading {
+            reload_timer: Timer::from_seconds(time, TimerMode::Once),
+        }
+    }
+
+    /// Advances the weapon by `delta` with the trigger held or not, and returns what happened.
+    /// Reloading and recharging go `reload_speed` times as fast.
+    fn update(
+        &mut self,
+        trigger: bool,
+        delta: Duration,
+        reload_speed: f32,
+    ) -> Option<WeaponAction> {
+        let reload_delta = delta.mul_f32(reload_speed);
+        if let ReloadStyle::Recharge { .. } = self.def.reload {
+            if self.rounds < self.def.magazine {
+                if self.recharge_timer.tick(reload_delta).just_finished() {
+                    self.rounds += 1;
+                }
+            } else {
+                self.recharge_timer.reset();
+            }
+        }
+
+        if !trigger && !matches!(self.def.fire_mode, FireMode::PressAndRelease) {
+            self.awaiting_release = false;
+        }
+        match self.state {
+            WeaponState::Idle => {
+                if !self.should_fire(trigger) {
+                    return None;
+                }
+                self.fire(trigger);
+                Some(WeaponAction::Fire)
+            }
+
+            WeaponState::Firing {
+                ref mut firing_timer,
+            } => {
+                firing_timer.tick(delta);
+                if !firing_timer.finished() {
+                    return None;
+                }
+                self.state = self.next_state.clone();
+                matches!(self.next_state, WeaponState::Reloading { .. })
+                    .then_some(WeaponAction::Reload)
+            }
+            WeaponState::Reloading {
+                ref mut reload_timer,
+            } => {
+                reload_timer.tick(reload_delta);
+                if !reload_timer.finished() {
+                    return None;
+                }
+                match self.def.reload {
+                    ReloadStyle::Full { .. } | ReloadStyle::Recharge { .. } => {
+                        self.rounds = self.def.magazine;
+                        self.awaiting_release = false;
+                        self.state = WeaponState::Idle;
+                        None
+                    }
+                    ReloadStyle::PerShell { .. } => {
+                        self.rounds += 1;
+                        // stop as soon as it's full or the player wants to shoot
+                        if self.rounds >= self.def.magazine || trigger {
+                            self.awaiting_release = false;
+                            self.state = WeaponState::Idle;
+                            None
+                        } else {
+                            self.state = self.reload_state();
+                            Some(WeaponAction::Reload)
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Default for Weapon {
+    /// The built-in [`WeaponLoadout`]'s primary.
+    fn default() -> Self {
+        Weapon::new(WeaponLoadout::default().primary)
+    }
+}
+
+/// Weapons the entity can switch its [`Weapon`] between. The active one lives in the
+/// [`Weapon`] component, the rest are holstered here with their ammo and reload progress kept
+/// as they were.
+#[derive(Debug, Component)]
+pub struct WeaponInventory {
+    /// One entry per slot, `None` for the active one.
+    slots: Vec<Option<Weapon>>,
+    active: usize,
+    switch: Option<WeaponSwitch>,
+}
+
+/// A switch in progress. The weapons are swapped halfway through.
+#[derive(Debug, Clone)]
+pub struct WeaponSwitch {
+    pub to: usize,
+    pub timer: Timer,
+    swapped: bool,
+}
+
+impl WeaponInventory {
+    /// Seconds to lower one weapon and raise the next.
+    pub const SWITCH_TIME: f32 = 0.4;
+
+    /// An inventory whose first slot is the entity's current [`Weapon`], followed by `holstered`.
+    pub fn new(holstered: impl IntoIterator<Item = WeaponDef>) -> Self {
+        let slots = std::iter::once(None)
+            .chain(holstered.into_iter().map(|def| Some(Weapon::new(def))))
+            .collect();
+        WeaponInventory {
+            slots,
+            active: 0,
+            switch: None,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    /// The slot of the weapon in the [`Weapon`] component.
+    pub fn active(&self) -> usize {
+        self.active
+    }
+
+    pub fn switching(&self) -> Option<&WeaponSwitch> {
+        self.switch.as_ref()
+    }
+
+    /// The holstered weapon in `slot`, or `None` for the active slot.
+    pub fn holstered(&self, slot: usize) -> Option<&Weapon> {
+        self.slots.get(slot)?.as_ref()
+    }
+
+    /// Every holstered weapon.
+    pub fn holstered_mut(&mut self) -> impl Iterator<Item = &mut Weapon> {
+        self.slots.iter_mut().flatten()
+    }
+
+    /// Starts switching to `slot`. Ignored if it's already active, doesn't exist, or another
+    /// switch is underway.
+    pub fn switch_to(&mut self, slot: usize) {
+        if slot == self.active || slot >= self.slots.len() || self.switch.is_some() {
+            return;
+        }
+        self.switch = Some(WeaponSwitch {
+            to: slot,
+            timer: Timer::from_seconds(Self::SWITCH_TIME, TimerMode::Once),
+            swapped: false,
+        });
+    }
+
+    /// Starts switching `offset` slots along, wrapping around.
+    pub fn cycle(&mut self, offset: isize) {
+        let len = self.slots.len() as isize;
+        if len == 0 {
+            return;
+        }
+        let slot = (self.active as isize + offset).rem_euclid(len);
+        self.switch_to(slot as usize);
+    }
+
+    /// Advances the switch, swapping `weapon` with the incoming one halfway through.
+    fn update(&mut self, weapon: &mut Weapon, delta: Duration) {
+        let Some(ref mut switch) = self.switch else {
+            return;
+        };
+        switch.timer.tick(delta);
+        if !switch.swapped && switch.timer.fraction() >= 0.5 {
+            switch.swapped = true;
+            let incoming = self.slots[switch.to]
+                .take()
+                .expect("switching to the active slot");
+            self.slots[self.active] = Some(std::mem::replace(weapon, incoming));
+            self.active = switch.to;
+        }
+        if switch.timer.finished() {
+            self.switch = None;
+        }
+    }
+}
+
+impl Default for WeaponInventory {
+    /// Goes with the default [`Weapon`], holding the rest of the built-in [`WeaponLoadout`].
+    fn default() -> Self {
+        WeaponInventory::new(WeaponLoadout::default().holstered)
+    }
+}
+
+/// A second [`Weapon`] fired with the alternate fire buttons.
+#[derive(Debug, Component)]
+pub struct AltWeapon(pub Weapon);
+
+impl Default for AltWeapon {
+    /// The built-in [`WeaponLoadout`]'s alt.
+    fn default() -> Self {
+        AltWeapon(Weapon::new(WeaponLoadout::default().alt))
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub enum WeaponState {
+    #[default]
+    Idle,
+    Firing {
+        firing_timer: Timer,
+    },
+    Reloading {
+        reload_timer: Timer,
+    },
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum WeaponAction {
+    Fire,
+    Reload,
+}
+
+fn switch_weapons(
+    time: Res<Time>,
+    input: Res<ActionState<InputAction>>,
+    mut query: Query<(&mut WeaponInventory, &mut Weapon, &Player)>,
+) {
+    let selected = [
+        InputAction::SelectWeapon1,
+        InputAction::SelectWeapon2,
+        InputAction::SelectWeapon3,
+    ]
+    .iter()
+    .position(|action| input.just_pressed(action));
+    for (mut inventory, mut weapon, player) in query.iter_mut() {
+        if player.dead {
+            continue;
+        }
+        if let Some(slot) = selected {
+            inventory.switch_to(slot);
+        } else if input.just_pressed(&InputAction::NextWeapon) {
+            inventory.cycle(1);
+        } else if input.just_pressed(&InputAction::PreviousWeapon) {
+            inventory.cycle(-1);
+        }
+        inventory.update(&mut weapon, time.delta());
+    }
+}
+
+fn update_weapons(
+    time: Res<Time>,
+    input: Res<ActionState<InputAction>>,
+    input_state: Res<InputState>,
+    upgrades: Res<Upgrades>,
+    mut query: Query<(
+        Entity,
+        Option<&mut Weapon>,
+        Option<&mut AltWeapon>,
+        Option<&WeaponInventory>,
+        Has<RapidReload>,
+        &Player,
+    )>,
+    mut writer: EventWriter<WeaponEvent>,
+) {
+    let trigger = input.pressed(&InputAction::FireSpace)
+        || (input.pressed(&InputAction::FireMouse) && input_state.locked_cursor);
+    let alt_trigger = input.pressed(&InputAction::AltFireKey)
+        || (input.pressed(&InputAction::AltFireMouse) && input_state.locked_cursor);
+    for (entity, weapon, alt_weapon, inventory, rapid_reload, player) in query.iter_mut() {
+        if player.dead {
+            continue;
+        }
+        let reload_speed = if rapid_reload {
+            RapidReload::SPEED * upgrades.reload_speed()
+        } else {
+            upgrades.reload_speed()
+        };
+        // the primary weapon is put away while switching
+        let switching = inventory.is_some_and(|inventory| inventory.switching().is_some());
+        let slots = [
+            (
+                WeaponSlot::Primary,
+                weapon.filter(|_| !switching).map(Mut::into_inner),
+                trigger,
+            ),
+            (
+                WeaponSlot::Alt,
+                alt_weapon.map(|alt| &mut alt.into_inner().0),
+                alt_trigger,
+            ),
+        ];
+        for (slot, weapon, trigger) in slots {
+            let Some(weapon) = weapon else {
+                continue;
+            };
+            match weapon.update(trigger, time.delta(), reload_speed) {
+                Some(WeaponAction::Fire) => writer.write(WeaponEvent::Fire { entity, slot }),
+                Some(WeaponAction::Reload) => writer.write(WeaponEvent::Reload { entity, slot }),
+                None => continue,
+            };
+        }
+    }
+}
+
+/// A pellet from a weapon with [`Delivery::Projectile`].
+#[derive(Debug, Component)]
+#[require(Visibility)]
+pub struct WeaponProjectile {
+    pub velocity: Vec3,
+    pub radius: f32,
+    pub damage: f32,
+    pub damage_type: DamageType,
+    pub falloff: RangeFalloff,
+    pub explosion: Option<ImpactExplosion>,
+    /// Whoever fired it. Left out of its explosion.
+    pub owner: Entity,
+    /// Where it was fired from.
+    pub origin: Vec3,
+    pub lifetime: Timer,
+}
+
+/// A pellet hit something. Sent for walls too, which don't take damage.
+#[derive(Debug, Clone, Copy, Event)]
+pub struct PelletImpact {
+    pub entity: Entity,
+    pub pos: Vec3,
+    /// Direction the pellet was travelling in.
+    pub dir: Vec3,
+    /// Damage dealt after falloff, or 0 if `entity` can't be damaged.
+    pub damage: f32,
+}
+
+fn pellet_filter() -> QueryFilter<'static> {
+    QueryFilter::new().groups(CollisionGroups {
+        memberships: WEAPON_GROUP,
+        filters: ENEMY_GROUP | PROJECTILE_GROUP | WALL_GROUP,
+    })
+}
+
+fn fire_weapons(
+    mut commands: Commands,
+    mut reader: EventReader<WeaponEvent>,
+    weapon_query: Query<(
+        &GlobalTransform,
+        &ReadHeading,
+        Option<&Weapon>,
+        Option<&AltWeapon>,
+        Has<DoubleDamage>,
+    )>,
+    health_query: Query<(), With<Health>>,
+    read_rapier_context: ReadRapierContext,
+    mut writer: EventWriter<DamageEvent>,
+    mut impact_writer: EventWriter<PelletImpact>,
+    time: Res<Time>,
+    mut chains: ResMut<ChainTracker>,
+    mut stats: ResMut<RunStats>,
+    mut rng: ResMut<StreamRng<WeaponRng>>,
+) {
+    if reader.is_empty() {
+        return;
+    }
+
+    let Ok(context) = read_rapier_context.single() else {
+        error!("Failed to get rapier context");
+        return;
+    };
+
+    for ev in reader.read() {
+        let &WeaponEvent::Fire { entity, slot } = ev else {
+            continue;
+        };
+        let Ok((transform, heading, weapon, alt_weapon, double_damage)) = weapon_query.get(entity)
+        else {
+            continue;
+        };
+        let weapon = match slot {
+            WeaponSlot::Primary => weapon,
+            WeaponSlot::Alt => alt_weapon.map(|alt| &alt.0),
+        };
+        let Some(weapon) = weapon else {
+            continue;
+        };
+        let def = &weapon.def;
+        let damage_multiplier = if double_damage {
+            DoubleDamage::MULTIPLIER
+        } else {
+            1.0
+        };
+        stats.shots_fired += 1;
+        let pos = transform.translation();
+
+        // every pellet of a round belongs to the same chain
+        let mut chain_id = None;
+        for dir in def.pellet_directions(heading.to_vec3(), &mut rng) {
+            match def.delivery {
+                Delivery::Hitscan { radius } => {
+                    let shape = Collider::ball(radius);
+                    let options = ShapeCastOptions::default();
+                    let Some((target, hit)) = context.cast_shape(
+                        pos,
+                        Rot::IDENTITY,
+                        dir,
+                        &shape,
+                        options,
+                        pellet_filter(),
+                    ) else {
+                        continue;
+                    };
+                    let dist = hit.time_of_impact;
+                    let damage = if health_query.contains(target) {
+                        def.damage * damage_multiplier * def.falloff.sample(dist)
+                    } else {
+                        0.0
+                    };
+                    impact_writer.write(PelletImpact {
+                        entity: target,
+                        pos: pos + dir * dist,
+                        dir,
+                        damage,
+                    });
+                    if damage <= 0.0 {
+                        continue;
+                    }
+                    trace!(
+                        "hit entity {:?} at a distance of {} for {} damage",
+                        target, dist, damage
+                    );
+                    let chain_id = *chain_id.get_or_insert_with(|| {
+                        chains.start(ChainRoot::Shot { origin: pos }, time.elapsed())
+                    });
+                    writer.write(DamageEvent {
+                        entity: target,
+                        damage,
+                        damage_type: def.damage_type,
+                        chain: 0,
+                        chain_id,
+                    });
+                }
+                Delivery::Projectile {
+                    speed,
+                    radius,
+                    lifetime,
+                    explosion,
+                } => {
+                    commands.spawn((
+                        WeaponProjectile {
+                            velocity: dir * speed,
+                            radius,
+                            damage: def.damage * damage_multiplier,
+                            damage_type: def.damage_type,
+                            falloff: def.falloff,
+                            explosion: explosion.map(|explosion| ImpactExplosion {
+                                damage: explosion.damage * damage_multiplier,
+                                ..explosion
+                            }),
+                            owner: entity,
+                            origin: pos,
+                            lifetime: Timer::from_seconds(lifetime, TimerMode::Once),
+                        },
+                        Transform::from_translation(pos),
+                        StateScoped(GameState::InGame),
+                    ));
+                }
+            }
+        }
+        if chain_id.is_some() {
+            stats.shots_hit += 1;
+        }
+    }
+}
+
+fn move_weapon_projectiles(
+    mut commands: Commands,
+    time: Res<Time>,
+    read_rapier_context: ReadRapierContext,
+    health_query: Query<(), With<Health>>,
+    mut writer: EventWriter<DamageEvent>,
+    mut impact_writer: EventWriter<PelletImpact>,
+    mut explosion_writer: EventWriter<ExplosionEvent>,
+    mut chains: ResMut<ChainTracker>,
+    mut stats: ResMut<RunStats>,
+    mut query: Query<(Entity, &mut Transform, &mut WeaponProjectile)>,
+) {
+    let Ok(context) = read_rapier_context.single() else {
+        error!("Failed to get rapier context");
+        return;
+    };
+
+    for (entity, mut transform, mut projectile) in query.iter_mut() {
+        projectile.lifetime.tick(time.delta());
+        if projectile.lifetime.finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        // sweep along this frame's movement so fast projectiles can't skip over anything
+        let shape = Collider::ball(projectile.radius);
+        let options = ShapeCastOptions::with_max_time_of_impact(time.delta_secs());
+        let pos = transform.translation;
+        let Some((target, hit)) = context.cast_shape(
+            pos,
+            Rot::IDENTITY,
+            projectile.velocity,
+            &shape,
+            options,
+            pellet_filter(),
+        ) else {
+            transform.translation += projectile.velocity * time.delta_secs();
+            continue;
+        };
+        commands.entity(entity).despawn();
+
+        let impact_pos = pos + projectile.velocity * hit.time_of_impact;
+        let hit_health = health_query.contains(target);
+        let damage = if hit_health {
+            projectile.damage
+                * projectile
+                    .falloff
+                    .sample(impact_pos.distance(projectile.origin))
+        } else {
+            0.0
+        };
+        impact_writer.write(PelletImpact {
+            entity: target,
+            pos: impact_pos,
+            dir: projectile.velocity.normalize_or_zero(),
+            damage,
+        });
+        if damage <= 0.0 && projectile.explosion.is_none() {
+            continue;
+        }
+
+        if hit_health {
+            stats.shots_hit += 1;
+        }
+        let chain_id = chains.start(
+            ChainRoot::Shot {
+                origin: projectile.origin,
+            },
+            time.elapsed(),
+        );
+        if damage > 0.0 {
+            writer.write(DamageEvent {
+                entity: target,
+                damage,
+                damage_type: projectile.damage_type,
+                chain: 0,
+                chain_id,
+            });
+        }
+        if let Some(explosion) = projectile.explosion {
+            // anything it kills counts as shot directly, so its own explosion is the first link
+            explosion_writer.write(ExplosionEvent {
+                pos: impact_pos,
+                scale: explosion.scale,
+                damage: explosion.damage,
+                falloff: explosion.falloff,
+                damage_type: explosion.damage_type,
+                occlusion: Occlusion::default(),
+                exclude: Some(projectile.owner),
+                chain: 0,
+                chain_id,
+            });
+        }
+    }
+}
+
+fn animate_view_model(
+    player_query: Query<(&Weapon, Option<&WeaponInventory>, &Player)>,
+    mut view_model_query: Query<(
+        &WeaponViewModel,
+        &mut Sprite3d,
+        &mut Transform,
+        &mut Visibility,
+    )>,
+) {
+    let Ok((weapon, inventory, player)) = player_query.single() else {
+        warn!("didn't find exactly one player with a weapon");
+        return;
+    };
+    // the view model is hidden when the player dies
+    if player.dead {
+        return;
+    }
+    let active = inventory.map_or(0, WeaponInventory::active);
+    // lower the old weapon out of view, then raise the new one
+    let drop = inventory
+        .and_then(WeaponInventory::switching)
+        .map_or(0.0, |switch| {
+            1.0 - (switch.timer.fraction() * 2.0 - 1.0).abs()
+        });
+    for (view_model, mut sprite, mut transform, mut visibility) in view_model_query.iter_mut() {
+        if view_model.slot != active {
+            visibility.set_if_neq(Visibility::Hidden);
+            continue;
+        }
+        visibility.set_if_neq(Visibility::Inherited);
+        transform.translation.y = -WeaponViewModel::SWITCH_DROP * drop;
+
+        let Some(ref mut atlas) = sprite.texture_atlas else {
+            warn!("Weapon sprite doesn't have a texture atlas");
+            continue;
+        };
+        let frames = weapon.def.frames;
+        atlas.index = match weapon.state {
+            WeaponState::Idle => frames.idle,
+            WeaponState::Firing { .. } => frames.firing,
+            WeaponState::Reloading { .. } => frames.reloading,
+        };
+    }
+}
+
+#[derive(AssetCollection, Resource, Debug)]
+struct WeaponAssets {
+    #[asset(path = "textures/flame_fire.dds")]
+    fire_atlas_texture: Handle<Image>,
+    #[asset(texture_atlas_layout(tile_size_x = 128, tile_size_y = 128, columns = 6, rows = 5))]
+    fire_atlas_layout: Handle<TextureAtlasLayout>,
+}
+
+fn spawn_weapon_projectile_visuals(
+    mut commands: Commands,
+    assets: Res<WeaponAssets>,
+    query: Query<Entity, Added<WeaponProjectile>>,
+    mut sprite3d_params: Sprite3dParams,
+) {
+    for entity in query.iter() {
+        let atlas = TextureAtlas {
+            layout: assets.fire_atlas_layout.clone(),
+            index: 0,
+        };
+        let animation = AnimatedSprite3d {
+            current: 0,
+            frames: vec![13, 14, 15, 16, 17, 18, 19, 20, 21],
+            timer: Timer::new(Duration::from_millis(60), TimerMode::Repeating),
+            destroy_when_finished: false,
+        };
+        let fire = Sprite3dBuilder {
+            image: assets.fire_atlas_texture.clone(),
+            alpha_mode: AlphaMode::Blend,
+            unlit: true,
+            pixels_per_metre: 256.0,
+            ..Default::default()
+        }
+        .bundle_with_atlas(&mut sprite3d_params, atlas);
+        let visual = commands
+            .spawn((fire, animation, FaceCamera::default()))
+            .id();
+
+        if let Ok(mut c) = commands.get_entity(entity) {
+            c.add_child(visual);
+        } else {
+            commands.entity(visual).despawn();
+        }
+    }
+}
+
+/// A puff of fire wherever a pellet hits something.
+fn spawn_pellet_impacts(
+    mut commands: Commands,
+    assets: Res<WeaponAssets>,
+    mut reader: EventReader<PelletImpact>,
+    mut sprite3d_params: Sprite3dParams,
+) {
+    for impact in reader.read() {
+        let atlas = TextureAtlas {
+            layout: assets.fire_atlas_layout.clone(),
+            index: 13,
+        };
+        let animation = AnimatedSprite3d {
+            current: 0,
+            frames: vec![13, 14, 15, 16, 17, 18, 19, 20, 21],
+            timer: Timer::new(Duration::from_millis(20), TimerMode::Repeating),
+            destroy_when_finished: true,
+        };
+        // hits that hurt something flare up more than ones that just hit a wall
+        let pixels_per_metre = if impact.damage > 0.0 { 384.0 } else { 640.0 };
+        let puff = Sprite3dBuilder {
+            image: assets.fire_atlas_texture.clone(),
+            alpha_mode: AlphaMode::Blend,
+            unlit: true,
+            pixels_per_metre,
+            ..Default::default()
+        }
+        .bundle_with_atlas(&mut sprite3d_params, atlas);
+        commands.spawn((
+            puff,
+            animation,
+            FaceCamera::default(),
+            // pulled back along the pellet's path so it isn't hidden inside what it hit
+            Transform::from_translation(impact.pos - impact.dir * 0.2),
+            StateScoped(GameState::InGame),
+        ));
+    }
+}
+
+// use render layer 1 for view model stuff
+#[derive(Debug, Default, Component)]
+#[require(Camera3d, Camera { order: 1, ..Default::default() }, RenderLayers::layer(1), Projection::Orthographic(OrthographicProjection {
+    scaling_mode: bevy::render::camera::ScalingMode::FixedVertical { viewport_height: 9.0 },
+    ..OrthographicProjection::default_3d()
+}))]
+struct ViewmodelCamera;
+
+/// The sprite of the weapon in inventory `slot`. Only the active one is shown.
+#[derive(Debug, Default, Component)]
+pub struct WeaponViewModel {
+    pub slot: usize,
+}
+
+impl WeaponViewModel {
+    /// How far the sprite drops out of view while switching weapons.
+    const SWITCH_DROP: f32 = 4.0;
+}
+
+fn setup_view_model(mut commands: Commands) {
+    commands.spawn((ViewmodelCamera, StateScoped(GameState::InGame)));
+}
+
+fn spawn_view_models(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut layouts: ResMut<Assets<TextureAtlasLayout>>,
+    query: Query<(&Weapon, Option<&WeaponInventory>), (Added<Weapon>, With<Player>)>,
+) {
+    for (weapon, inventory) in query.iter() {
+        let slots = inventory.map_or(1, WeaponInventory::len);
+        for slot in 0..slots {
+            let def = match inventory.and_then(|inventory| inventory.holstered(slot)) {
+                Some(holstered) => &holstered.def,
+                None => &weapon.def,
+            };
+            commands.spawn((
+                PendingViewModel {
+                    image: asset_server.load(&def.view_model.image),
+                    layout: layouts.add(def.view_model.layout()),
+                },
+                WeaponViewModel { slot },
+                RenderLayers::layer(1),
+                Transform::from_xyz(0.0, 0.0, -1.0),
+                StateScoped(GameState::InGame),
+            ));
+        }
+    }
+}
+
+/// A [`WeaponViewModel`] waiting for its image to load before it gets a sprite.
+#[derive(Debug, Component)]
+struct PendingViewModel {
+    image: Handle<Image>,
+    layout: Handle<TextureAtlasLayout>,
+}
+
+fn build_view_models(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    query: Query<(Entity, &PendingViewModel)>,
+    mut sprite3d_params: Sprite3dParams,
+) {
+    for (entity, pending) in query.iter() {
+        if !asset_server.is_loaded_with_dependencies(&pending.image) {
+            continue;
+        }
+        let atlas = TextureAtlas {
+            layout: pending.layout.clone(),
+            index: 0,
+        };
+        let weapon_sprite = Sprite3dBuilder {
+            image: pending.image.clone(),
+            alpha_mode: AlphaMode::Blend,
+            unlit: true,
+            pixels_per_metre: 24.0,
+            ..Default::default()
+        }
+        .bundle_with_atlas(&mut sprite3d_params, atlas);
+        commands
+            .entity(entity)
+            .remove::<PendingViewModel>()
+            .insert(weapon_sprite);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TICK: Duration = Duration::from_millis(10);
+
+    /// Steps `weapon` until it stops being busy, and returns everything it did on the way.
+    fn settle(weapon: &mut Weapon, trigger: bool) -> Vec<WeaponAction> {
+        let mut actions = Vec::new();
+        for _ in 0..1000 {
+            actions.extend(weapon.update(trigger, TICK, 1.0));
+            if matches!(weapon.state, WeaponState::Idle) {
+                return actions;
+            }
+        }
+        panic!("{} never settled", weapon.def.name);
+    }
+
+    #[test]
+    fn shotgun_fires_a_barrel_on_press_and_release_then_reloads() {
+        let mut shotgun = Weapon::new(WeaponLoadout::default().primary);
+        assert_eq!(shotgun.update(true, TICK, 1.0), Some(WeaponAction::Fire));
+        assert!(settle(&mut shotgun, true).is_empty());
+        // holding on doesn't fire the second barrel
+        assert_eq!(shotgun.update(true, TICK, 1.0), None);
+        assert_eq!(shotgun.update(false, TICK, 1.0), Some(WeaponAction::Fire));
+        assert_eq!(shotgun.rounds, 0);
+        assert_eq!(settle(&mut shotgun, false), [WeaponAction::Reload]);
+        assert_eq!(shotgun.rounds, shotgun.def.magazine);
+    }
+
+    #[test]
+    fn repeater_empties_while_held_then_reloads_a_round_at_a_time() {
+        let mut repeater = Weapon::new(WeaponLoadout::default().holstered.remove(0));
+        let mut fired = 0;
+        while repeater.rounds > 0 {
+            if repeater.update(true, TICK, 1.0) == Some(WeaponAction::Fire) {
+                fired += 1;
+            }
+        }
+        assert_eq!(fired, repeater.def.magazine);
+        // one to start reloading, then one more after each round that doesn't fill it
+        let reloads = settle(&mut repeater, false);
+        assert_eq!(reloads.len() as u32, repeater.def.magazine);
+        assert_eq!(repeater.rounds, repeater.def.magazine);
+    }
+
+    #[test]
+    fn slug_recharges_a_round_at_a_time() {
+        let mut slug = Weapon::new(WeaponLoadout::default().alt);
+        let ReloadStyle::Recharge { time } = slug.def.reload else {
+            panic!("the slug should recharge");
+        };
+        assert_eq!(slug.update(true, TICK, 1.0), Some(WeaponAction::Fire));
+        assert!(settle(&mut slug, false).is_empty());
+        assert_eq!(slug.rounds, slug.def.magazine - 1);
+
+        slug.update(false, Duration::from_secs_f32(time), 1.0);
+        assert_eq!(slug.rounds, slug.def.magazine);
+    }
+
+    #[test]
+    fn slugs_explode_with_fire() {
+        let Delivery::Projectile {
+            explosion: Some(explosion),
+            ..
+        } = WeaponLoadout::default().alt.delivery
+        else {
+            panic!("the slug should be an explosive projectile");
+        };
+        assert_eq!(explosion.damage_type, DamageType::Fire);
+    }
+}
@@ -0,0 +1,65 @@
+use bevy::prelude::*;
+use exploding_skulls::{
+    chain::ChainTracker,
+    enemy::EnemyKind,
+    headless::{HeadlessAppExt, headless_app},
+    score::{RunStats, Score, ScoringRules},
+    spawner::SkullsKilled,
+};
+
+/// Close enough for a fire skull's explosion to kill its neighbour, but not the one after it.
+const SPACING: f32 = 1.2;
+/// Give up on chains that never settle.
+const MAX_TICKS: u32 = 600;
+
+#[test]
+fn line_of_skulls_chains_one_at_a_time() {
+    const SKULLS: usize = 3;
+
+    let mut app = headless_app();
+    app.start_game_with_seed(0);
+
+    // a line across the player's view, away from its explosions
+    let entities = app.spawn_settled(
+        EnemyKind::FireSkull,
+        (0..SKULLS).map(|i| Vec3::new(i as f32 * SPACING, 0.0, -8.0)),
+    );
+
+    let kills_before = app.world().resource::<SkullsKilled>().count;
+    let score_before = app.world().resource::<Score>().score;
+    app.detonate(entities[0]);
+
+    for _ in 0..MAX_TICKS {
+        app.step_ticks(1);
+        if app
+            .world()
+            .resource::<ChainTracker>()
+            .active()
+            .next()
+            .is_none()
+        {
+            break;
+        }
+    }
+    // the chain bonus is paid out for the `ChainFinished` event
+    app.step_ticks(1);
+
+    let kills = app.world().resource::<SkullsKilled>().count - kills_before;
+    assert_eq!(kills, SKULLS);
+    assert_eq!(
+        app.world().resource::<RunStats>().longest_chain,
+        SKULLS as u64
+    );
+
+    // one kill at each depth, each one a step further up the combo
+    let rules = ScoringRules::default();
+    let value = EnemyKind::FireSkull.archetype().score;
+    let mut multiplier = 1.0;
+    let mut expected = rules.chain_bonus(SKULLS as u32);
+    for depth in 0..SKULLS as u64 {
+        expected += rules.kill_points(value, depth, multiplier);
+        multiplier += rules.combo_step;
+    }
+    let score = app.world().resource::<Score>().score - score_before;
+    assert_eq!(score, expected);
+}
@@ -0,0 +1,76 @@
+use std::collections::VecDeque;
+
+use exploding_skulls::pickup::{DropTable, PickupKind};
+use rand_core::RngCore;
+
+/// Hands out fractions of `u32::MAX`, in order, and counts how many were drawn.
+struct Draws {
+    queue: VecDeque<f64>,
+    drawn: usize,
+}
+
+impl Draws {
+    fn new(fractions: &[f64]) -> Self {
+        Draws {
+            queue: fractions.iter().copied().collect(),
+            drawn: 0,
+        }
+    }
+}
+
+impl RngCore for Draws {
+    fn next_u32(&mut self) -> u32 {
+        self.drawn += 1;
+        let fraction = self.queue.pop_front().expect("drew more than expected");
+        (fraction * u32::MAX as f64) as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.next_u32() as u64
+    }
+
+    fn fill_bytes(&mut self, dst: &mut [u8]) {
+        for byte in dst {
+            *byte = self.next_u32() as u8;
+        }
+    }
+}
+
+fn health_or_gem() -> Vec<(PickupKind, f32)> {
+    vec![(PickupKind::Health, 1.0), (PickupKind::ScoreGem, 3.0)]
+}
+
+#[test]
+fn no_chance_never_drops_or_draws() {
+    let mut rng = Draws::new(&[]);
+    assert_eq!(DropTable::default().roll(&mut rng), None);
+    assert_eq!(DropTable::new(-1.0).roll(&mut rng), None);
+    assert_eq!(rng.drawn, 0);
+}
+
+#[test]
+fn missed_chance_drops_nothing() {
+    let table = DropTable {
+        chance: 0.25,
+        weights: health_or_gem(),
+    };
+    let mut rng = Draws::new(&[0.5]);
+    assert_eq!(table.roll(&mut rng), None);
+    assert_eq!(rng.drawn, 1);
+}
+
+#[test]
+fn picks_by_weight() {
+    let table = DropTable {
+        chance: 1.0,
+        weights: health_or_gem(),
+    };
+    // the first draw is the chance, the second picks out of a total weight of 4
+    let roll = |pick: f64| table.roll(&mut Draws::new(&[0.0, pick]));
+    assert_eq!(roll(0.0), Some(PickupKind::Health));
+    assert_eq!(roll(0.2), Some(PickupKind::Health));
+    assert_eq!(roll(0.3), Some(PickupKind::ScoreGem));
+    assert_eq!(roll(0.9), Some(PickupKind::ScoreGem));
+    // the very top of the range falls through to the last entry
+    assert_eq!(roll(1.0), Some(PickupKind::ScoreGem));
+}
@@ -0,0 +1,57 @@
+use bevy::prelude::*;
+use exploding_skulls::{
+    chain::ChainRoot,
+    enemy::EnemyKind,
+    explosion::{ExplosionEvent, Falloff, Occlusion},
+    headless::{HeadlessAppExt, headless_app},
+    health::{DamageType, Health},
+    level::ARENA_HALF_SIZE,
+};
+
+/// Sets off a blocking explosion at `blast` next to a skull at `skull`, and returns whether the
+/// skull died.
+fn blast_kills(blast: Vec3, skull: Vec3) -> bool {
+    let mut app = headless_app();
+    app.start_game_with_seed(0);
+
+    let entity = app.spawn_settled(EnemyKind::FireSkull, [skull])[0];
+    let chain_id = app.start_chain(ChainRoot::Shot { origin: blast });
+    app.world_mut().send_event(ExplosionEvent {
+        pos: blast,
+        scale: 1.0,
+        damage: 100.0,
+        falloff: Falloff::Linear,
+        damage_type: DamageType::Fire,
+        occlusion: Occlusion::Block,
+        exclude: None,
+        chain: 1,
+        chain_id,
+    });
+    app.step_ticks(2);
+
+    app.world()
+        .get::<Health>(entity)
+        .is_none_or(|health| health.dead)
+}
+
+#[test]
+fn open_arena_blast_kills() {
+    assert!(blast_kills(
+        Vec3::new(0.0, 0.0, -10.0),
+        Vec3::new(1.0, 0.0, -10.0)
+    ));
+}
+
+#[test]
+fn wall_blocks_blast_from_inside_the_arena() {
+    let blast = Vec3::new(ARENA_HALF_SIZE - 1.0, 0.0, 0.0);
+    let skull = Vec3::new(ARENA_HALF_SIZE + 1.0, 0.0, 0.0);
+    assert!(!blast_kills(blast, skull));
+}
+
+#[test]
+fn blast_inside_a_wall_is_not_blocked_by_it() {
+    let blast = Vec3::new(ARENA_HALF_SIZE + 10.0, 0.0, 0.0);
+    let skull = Vec3::new(ARENA_HALF_SIZE + 11.0, 0.0, 0.0);
+    assert!(blast_kills(blast, skull));
+}
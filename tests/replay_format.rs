@@ -0,0 +1,158 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use exploding_skulls::{
+    game_mode::GameMode,
+    input::InputSettings,
+    rand::RunSeed,
+    replay::{Replay, ReplayError, ReplayFrame},
+};
+
+const SEED: u64 = 0x5eed_1234_abcd;
+
+fn frames() -> Vec<ReplayFrame> {
+    vec![
+        ReplayFrame {
+            delta: Duration::from_micros(15625),
+            buttons: 0b101,
+            turn: 0.0,
+        },
+        ReplayFrame {
+            delta: Duration::from_micros(15625),
+            buttons: 0b1_0000_0000_0000_0100_0000,
+            turn: -0.25,
+        },
+    ]
+}
+
+/// Frames the way versions 1 and 2 stored them, with 16 bit buttons.
+fn v2_frame_bytes(frames: &[ReplayFrame]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for frame in frames {
+        bytes.extend_from_slice(&(frame.delta.as_micros() as u32).to_le_bytes());
+        bytes.extend_from_slice(&(frame.buttons as u16).to_le_bytes());
+        bytes.extend_from_slice(&frame.turn.to_le_bytes());
+    }
+    bytes
+}
+
+/// Frames as a version 1 or 2 replay would have read back: only 16 bits of buttons, and the raw
+/// mouse axis scaled by the default settings.
+fn legacy_frames() -> Vec<ReplayFrame> {
+    let settings = InputSettings::default();
+    let tick = Time::<Fixed>::default().timestep().as_secs_f32();
+    frames()
+        .into_iter()
+        .map(|frame| ReplayFrame {
+            buttons: frame.buttons & 0xffff,
+            turn: -frame.turn * settings.mouse_sensitivity * tick * settings.turn_rate,
+            ..frame
+        })
+        .collect()
+}
+
+#[test]
+fn v4_round_trips() {
+    let replay = Replay {
+        seed: RunSeed(SEED),
+        mode: GameMode::Waves,
+        frames: frames(),
+    };
+    let read = Replay::from_bytes(&replay.to_bytes()).unwrap();
+    assert_eq!(read.seed, replay.seed);
+    assert_eq!(read.mode, replay.mode);
+    assert_eq!(read.frames, replay.frames);
+}
+
+#[test]
+fn v2_reads_mode_and_16_bit_buttons() {
+    let mut bytes = b"ESRP".to_vec();
+    bytes.push(2);
+    bytes.extend_from_slice(&SEED.to_le_bytes());
+    bytes.push(GameMode::Waves as u8);
+    bytes.extend_from_slice(&(frames().len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&v2_frame_bytes(&frames()));
+
+    let read = Replay::from_bytes(&bytes).unwrap();
+    assert_eq!(read.seed, RunSeed(SEED));
+    assert_eq!(read.mode, GameMode::Waves);
+    assert_eq!(read.frames, legacy_frames());
+}
+
+#[test]
+fn v1_is_an_endless_run() {
+    let mut bytes = b"ESRP".to_vec();
+    bytes.push(1);
+    bytes.extend_from_slice(&SEED.to_le_bytes());
+    bytes.extend_from_slice(&(frames().len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&v2_frame_bytes(&frames()));
+
+    let read = Replay::from_bytes(&bytes).unwrap();
+    assert_eq!(read.seed, RunSeed(SEED));
+    assert_eq!(read.mode, GameMode::Endless);
+    assert_eq!(read.frames, legacy_frames());
+}
+
+#[test]
+fn legacy_key_turns_are_scaled() {
+    // turn left (bit 4) held for a tenth of a second
+    let frame = ReplayFrame {
+        delta: Duration::from_millis(100),
+        buttons: 1 << 4,
+        turn: 0.0,
+    };
+    let mut bytes = b"ESRP".to_vec();
+    bytes.push(3);
+    bytes.extend_from_slice(&SEED.to_le_bytes());
+    bytes.push(GameMode::Endless as u8);
+    bytes.extend_from_slice(&1u32.to_le_bytes());
+    bytes.extend_from_slice(&100_000u32.to_le_bytes());
+    bytes.extend_from_slice(&frame.buttons.to_le_bytes());
+    bytes.extend_from_slice(&frame.turn.to_le_bytes());
+
+    let read = Replay::from_bytes(&bytes).unwrap();
+    let expected = 0.1 * InputSettings::default().turn_rate;
+    assert!((read.frames[0].turn - expected).abs() < 1e-6);
+}
+
+#[test]
+fn shop_buttons_become_weapon_buttons() {
+    // ShopBuy2 (bit 17) clicked in the shop, with Dash (bit 20) held
+    let mut bytes = b"ESRP".to_vec();
+    bytes.push(4);
+    bytes.extend_from_slice(&SEED.to_le_bytes());
+    bytes.push(GameMode::Waves as u8);
+    bytes.extend_from_slice(&1u32.to_le_bytes());
+    bytes.extend_from_slice(&15625u32.to_le_bytes());
+    bytes.extend_from_slice(&((1u32 << 17) | (1 << 20)).to_le_bytes());
+    bytes.extend_from_slice(&0f32.to_le_bytes());
+
+    let read = Replay::from_bytes(&bytes).unwrap();
+    // SelectWeapon2 is bit 14
+    assert_eq!(read.frames[0].buttons, (1 << 14) | (1 << 20));
+}
+
+#[test]
+fn missing_frames_are_truncated() {
+    let replay = Replay {
+        seed: RunSeed(SEED),
+        mode: GameMode::Endless,
+        frames: frames(),
+    };
+    let bytes = replay.to_bytes();
+    assert_eq!(
+        Replay::from_bytes(&bytes[..bytes.len() - 1]).unwrap_err(),
+        ReplayError::Truncated
+    );
+
+    // claims far more frames than it has
+    let mut bytes = b"ESRP".to_vec();
+    bytes.push(4);
+    bytes.extend_from_slice(&SEED.to_le_bytes());
+    bytes.push(GameMode::Endless as u8);
+    bytes.extend_from_slice(&u32::MAX.to_le_bytes());
+    assert_eq!(
+        Replay::from_bytes(&bytes).unwrap_err(),
+        ReplayError::Truncated
+    );
+}